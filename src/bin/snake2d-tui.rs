@@ -0,0 +1,125 @@
+//! A snake2d game played in a terminal.
+
+use std::io::{self, Write};
+use std::process;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::{cursor, execute, queue, terminal};
+
+use snake2d::*;
+use snake2d::cli::Options;
+use snake2d::config::Config;
+use snake2d::game_controller::GameController;
+use snake2d::high_scores::{HighScoreEntry, HighScores};
+use snake2d::key_bindings::Action;
+use snake2d::renderer::Renderer;
+use snake2d::tui_view::{TuiView, TuiViewSettings};
+
+fn draw(out: &mut impl Write, view: &mut TuiView, controller: &GameController, high_scores: &[String])
+        -> io::Result<()> {
+    for (row, line) in view.render(&controller.frame()).iter().enumerate() {
+        queue!(out, cursor::MoveTo(0, row as u16))?;
+        write!(out, "{}", line)?;
+        queue!(out, terminal::Clear(terminal::ClearType::UntilNewLine))?;
+    }
+    let (_, height) = controller.game_logic.get_board_size();
+    queue!(out, cursor::MoveTo(0, height as u16 + 3))?;
+    let key_bindings = controller.key_bindings();
+    let moves: Vec<String> = [Direction::Up, Direction::Left, Direction::Down, Direction::Right].iter()
+        .map(|&direction| key_bindings.describe(Action::Move(direction)))
+        .collect();
+    write!(out, "{}: move, {}: pause, {}: step while paused, {}: restart, {}: quit", moves.join(" "),
+           key_bindings.describe(Action::Pause), key_bindings.describe(Action::Step),
+           key_bindings.describe(Action::Restart), key_bindings.describe(Action::Quit))?;
+    for (row, line) in high_scores.iter().enumerate() {
+        queue!(out, cursor::MoveTo(0, height as u16 + 5 + row as u16))?;
+        write!(out, "{}", line)?;
+    }
+    out.flush()
+}
+
+// returns the name of a key as used by key bindings
+fn key_name(code: KeyCode) -> Option<String> {
+    let name = match code {
+        KeyCode::Char(' ') => "Space",
+        KeyCode::Char(c) => return Some(c.to_string()),
+        KeyCode::Up => "Up",
+        KeyCode::Down => "Down",
+        KeyCode::Left => "Left",
+        KeyCode::Right => "Right",
+        KeyCode::Enter => "Enter",
+        KeyCode::Esc => "Escape",
+        KeyCode::Tab => "Tab",
+        KeyCode::Backspace => "Backspace",
+        _ => return None,
+    };
+    Some(String::from(name))
+}
+
+fn run(out: &mut impl Write, controller: &mut GameController, options: &Options, config: &Config) -> io::Result<()> {
+    let game_view_settings = config.view.settings().expect("View settings are checked with the configuration!");
+    let mut view = TuiView::new(TuiViewSettings::new(), game_view_settings);
+    let tick = |controller: &GameController| Duration::from_secs(1) / controller.game_logic.get_tick_rate() as u32;
+    let mut next_tick = Instant::now() + tick(controller);
+    let mode = options.mode(config);
+    let records_high_scores = options.records_high_scores();
+    let mut high_scores = Vec::new();
+
+    loop {
+        if records_high_scores && controller.game_logic.get_status().is_over() && high_scores.is_empty() {
+            let entry = HighScoreEntry::from_game(&controller.game_logic, &config.scores.player(), &mode);
+            high_scores = HighScores::record_default(config.scores.max_entries, entry).1;
+        }
+        draw(out, &mut view, controller, &high_scores)?;
+        let timeout = next_tick.saturating_duration_since(Instant::now());
+        if event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Release {
+                    continue;
+                }
+                let action = key_name(key.code).and_then(|name| controller.key_bindings().action(&name));
+                match action {
+                    Some(Action::Quit) => return Ok(()),
+                    Some(Action::Restart) => {
+                        controller.perform(Action::Restart);
+                        high_scores.clear();
+                        execute!(out, terminal::Clear(terminal::ClearType::All))?;
+                    },
+                    Some(action) => controller.perform(action),
+                    None => (),
+                }
+            }
+        }
+        if Instant::now() >= next_tick {
+            controller.update();
+            next_tick += tick(controller);
+        }
+    }
+}
+
+fn main() -> io::Result<()> {
+    let (options, config) = Options::from_args();
+    match options.run_command(&config) {
+        Ok(true) => return Ok(()),
+        Ok(false) => (),
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(1);
+        },
+    }
+    let mut controller = options.game_controller(&config).unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        process::exit(1);
+    });
+    eprintln!("Seed: {}", controller.game_logic.get_seed());
+    let mut out = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide, terminal::Clear(terminal::ClearType::All))?;
+    let result = run(&mut out, &mut controller, &options, &config);
+    execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+
+    options.save_recording(&controller);
+    result
+}
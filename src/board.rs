@@ -1,15 +1,24 @@
+use crate::Direction;
 use crate::Obstacle;
 use crate::Segment;
+use crate::Topology;
 
 pub struct Board {
     width: usize,
     height: usize,
     board: Vec<Vec<Obstacle>>,
     number_of_obstacles: usize,
+    number_of_walls: usize,
+    topology: Topology,
 }
 
 impl Board {
     pub fn new(width: usize, height: usize) -> Board {
+        Board::with_topology(width, height, Topology::Bounded)
+    }
+
+    #[allow(clippy::unnecessary_cast)]
+    pub fn with_topology(width: usize, height: usize, topology: Topology) -> Board {
         let mut column: Vec<Obstacle> = Vec::with_capacity(height as usize);
         column.resize(height as usize, Obstacle::None);
 
@@ -21,6 +30,8 @@ impl Board {
             height,
             board,
             number_of_obstacles: 0,
+            number_of_walls: 0,
+            topology,
         }
     }
 
@@ -46,14 +57,38 @@ impl Board {
         self.number_of_obstacles -= 1;
     }
 
+    /// Places a wall on an empty field.
+    pub fn set_wall(&mut self, x: usize, y: usize) {
+        assert_eq!(self.board[x][y], Obstacle::None);
+        self.board[x][y] = Obstacle::Wall;
+        self.number_of_walls += 1;
+    }
+
     pub fn segment_in(&self, segment: &Segment) -> bool {
         segment.x >= 0 && segment.x < self.width as i32 &&
             segment.y >= 0 && segment.y < self.height as i32
     }
 
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Returns the segment next to the given one in the given direction, wrapped around the
+    /// board edges if the board is toroidal.
+    pub fn neighbour(&self, segment: &Segment, direction: &Direction) -> Segment {
+        match self.topology {
+            Topology::Bounded => direction.nearest_segment(segment),
+            Topology::Toroidal => direction.nearest_segment_wrapped(segment, self.width, self.height),
+        }
+    }
+
     pub fn get_number_of_obstacles(&self) -> usize {
         self.number_of_obstacles
     }
+
+    pub fn get_number_of_walls(&self) -> usize {
+        self.number_of_walls
+    }
 }
 
 #[cfg(test)]
@@ -61,6 +96,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn given_board_when_segments_are_in_then_segment_in_returns_true() {
         let board = Board::new(3, 8);
         assert_eq!(board.segment_in(&Segment::new(0, 0)), true);
@@ -68,6 +104,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn given_board_when_segments_are_not_in_then_segment_in_returns_false() {
         let board = Board::new(8, 3);
         assert_eq!(board.segment_in(&Segment::new(-1, 0)), false);
@@ -108,6 +145,45 @@ mod tests {
         assert_eq!(board.get_field(18, 44), Obstacle::None);
     }
 
+    #[test]
+    fn given_walls_on_board_then_walls_are_counted_separately_from_obstacles() {
+        let mut board = Board::new(5, 5);
+        board.set_wall(0, 0);
+        board.set_wall(4, 3);
+        board.set_obstacle(2, 2, 1);
+
+        assert_eq!(board.get_field(0, 0), Obstacle::Wall);
+        assert_eq!(board.get_field(4, 3), Obstacle::Wall);
+        assert_eq!(board.get_number_of_walls(), 2);
+        assert_eq!(board.get_number_of_obstacles(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn given_obstacle_on_field_when_placing_wall_there_then_panic() {
+        let mut board = Board::new(5, 5);
+        board.set_obstacle(2, 2, 1);
+        board.set_wall(2, 2);
+    }
+
+    #[test]
+    fn given_bounded_board_when_leaving_board_then_neighbour_is_outside() {
+        let board = Board::new(4, 3);
+        assert_eq!(board.neighbour(&Segment::new(3, 1), &Direction::Right), Segment::new(4, 1));
+        assert_eq!(board.neighbour(&Segment::new(1, 0), &Direction::Up), Segment::new(1, -1));
+        assert_eq!(board.neighbour(&Segment::new(1, 1), &Direction::Down), Segment::new(1, 2));
+    }
+
+    #[test]
+    fn given_toroidal_board_when_leaving_board_then_neighbour_is_on_opposite_edge() {
+        let board = Board::with_topology(4, 3, Topology::Toroidal);
+        assert_eq!(board.neighbour(&Segment::new(3, 1), &Direction::Right), Segment::new(0, 1));
+        assert_eq!(board.neighbour(&Segment::new(0, 1), &Direction::Left), Segment::new(3, 1));
+        assert_eq!(board.neighbour(&Segment::new(1, 0), &Direction::Up), Segment::new(1, 2));
+        assert_eq!(board.neighbour(&Segment::new(1, 2), &Direction::Down), Segment::new(1, 0));
+        assert_eq!(board.neighbour(&Segment::new(1, 1), &Direction::Down), Segment::new(1, 2));
+    }
+
     #[test]
     #[should_panic]
     fn given_board_when_access_position_not_in_board_then_panic() {
@@ -0,0 +1,570 @@
+//! Computer players.
+//!
+//! A `Player` looks at a game through a shared reference to `GameLogic` and returns the move for
+//! the next tick, so the same bot can drive a `GameController` in a window, a `HeadlessRunner` or
+//! a test.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::game_logic::GameLogic;
+use crate::{Direction, Segment, Topology};
+
+/// Free fields kept between the head and the tail, on top of the snake length, when the
+/// Hamiltonian bot takes a shortcut.
+pub const SHORTCUT_BUFFER: usize = 3;
+
+/// Directions in the order bots try them.
+pub const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+
+/// Source of moves of a game.
+pub trait Player {
+    /// Returns the name of the player.
+    fn name(&self) -> &str;
+
+    /// Returns the move for the next tick, `None` keeps the snake moving in its direction.
+    fn next_direction(&mut self, game_logic: &GameLogic) -> Option<Direction>;
+}
+
+/// Snapshot of the board as seen by bots.
+#[derive(Clone, Debug)]
+pub struct Grid {
+    width: usize,
+    height: usize,
+    topology: Topology,
+    blocked: Vec<bool>,
+    fruit: Vec<Segment>,
+    head: Segment,
+    tail: Segment,
+    direction: Option<Direction>,
+    length: usize,
+    segments: usize,
+    walls: usize,
+}
+
+impl Grid {
+    /// Takes a snapshot of a game. Walls and the snake are blocked, except for the tail which
+    /// moves away on the next tick unless the snake is growing.
+    pub fn new(game_logic: &GameLogic) -> Grid {
+        let (width, height) = game_logic.get_board_size();
+        let mut blocked = vec![false; width * height];
+        let walls = game_logic.get_walls();
+        for &(x, y) in walls.iter() {
+            blocked[y * width + x] = true;
+        }
+        let snake = game_logic.get_snake_segments();
+        let growing = game_logic.get_length() > snake.len();
+        let moving_segments = if growing { snake.len() } else { snake.len() - 1 };
+        // the head is off the board after the snake has crashed into the border
+        let on_board = |segment: &&Segment| segment.x >= 0 && segment.y >= 0
+            && (segment.x as usize) < width && (segment.y as usize) < height;
+        for segment in snake.iter().take(moving_segments).filter(on_board) {
+            blocked[segment.y as usize * width + segment.x as usize] = true;
+        }
+        Grid {
+            width,
+            height,
+            topology: game_logic.get_topology(),
+            blocked,
+            fruit: game_logic.get_obstacles().iter().map(|&(x, y, _)| Segment::new(x as i32, y as i32)).collect(),
+            head: *snake.front().unwrap(),
+            tail: *snake.back().unwrap(),
+            direction: game_logic.get_direction(),
+            length: game_logic.get_length(),
+            segments: snake.len(),
+            walls: walls.len(),
+        }
+    }
+
+    /// Returns the board width and height.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Returns the snake head.
+    pub fn head(&self) -> Segment {
+        self.head
+    }
+
+    /// Returns the last segment of the snake.
+    pub fn tail(&self) -> Segment {
+        self.tail
+    }
+
+    /// Returns the length of the snake, including growth which is still pending.
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Returns the number of fields taken by the snake.
+    pub fn segments(&self) -> usize {
+        self.segments
+    }
+
+    /// Returns true if the board has no walls.
+    pub fn is_open(&self) -> bool {
+        self.walls == 0
+    }
+
+    /// Returns the direction the snake moves in.
+    pub fn direction(&self) -> Option<Direction> {
+        self.direction
+    }
+
+    /// Returns positions of fruit on the board.
+    pub fn fruit(&self) -> &[Segment] {
+        &self.fruit
+    }
+
+    /// Returns the field next to a segment, `None` if it lies beyond the edge of a bounded board.
+    pub fn neighbour(&self, segment: Segment, direction: Direction) -> Option<Segment> {
+        match self.topology {
+            Topology::Toroidal => Some(direction.nearest_segment_wrapped(&segment, self.width, self.height)),
+            Topology::Bounded => {
+                let neighbour = direction.nearest_segment(&segment);
+                if neighbour.x >= 0 && neighbour.x < self.width as i32 && neighbour.y >= 0 &&
+                        neighbour.y < self.height as i32 {
+                    Some(neighbour)
+                } else {
+                    None
+                }
+            },
+        }
+    }
+
+    /// Returns true if the snake can move onto the field.
+    pub fn is_free(&self, segment: Segment) -> bool {
+        !self.blocked[self.index(segment)]
+    }
+
+    /// Returns moves which do not end the game on the next tick, with the fields they lead to.
+    /// Reversing is never safe, the game ignores it.
+    pub fn safe_moves(&self) -> Vec<(Direction, Segment)> {
+        DIRECTIONS.iter()
+            .filter(|&&direction| self.direction != Some(direction.opposite()))
+            .filter_map(|&direction| self.neighbour(self.head, direction).map(|field| (direction, field)))
+            .filter(|&(_, field)| self.is_free(field))
+            .collect()
+    }
+
+    /// Returns the number of moves between two fields on an empty board.
+    pub fn distance(&self, from: Segment, to: Segment) -> usize {
+        let axis = |from: i32, to: i32, size: usize| {
+            let distance = (from - to).unsigned_abs() as usize;
+            match self.topology {
+                Topology::Bounded => distance,
+                Topology::Toroidal => distance.min(size - distance),
+            }
+        };
+        axis(from.x, to.x, self.width) + axis(from.y, to.y, self.height)
+    }
+
+    /// Returns the number of free fields reachable from a free field, including it.
+    pub fn reachable_area(&self, from: Segment) -> usize {
+        let mut visited = self.blocked.clone();
+        visited[self.index(from)] = true;
+        let mut queue = VecDeque::from(vec![from]);
+        let mut area = 0;
+        while let Some(segment) = queue.pop_front() {
+            area += 1;
+            for &direction in DIRECTIONS.iter() {
+                if let Some(next) = self.neighbour(segment, direction) {
+                    let index = self.index(next);
+                    if !visited[index] {
+                        visited[index] = true;
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        area
+    }
+
+    /// Returns the first move of a shortest path through free fields to the nearest fruit.
+    pub fn path_to_fruit(&self) -> Option<Direction> {
+        let mut first_moves: Vec<Option<Direction>> = vec![None; self.width * self.height];
+        let mut visited = self.blocked.clone();
+        let mut queue = VecDeque::new();
+        for (direction, field) in self.safe_moves() {
+            visited[self.index(field)] = true;
+            first_moves[self.index(field)] = Some(direction);
+            queue.push_back(field);
+        }
+        while let Some(segment) = queue.pop_front() {
+            let first_move = first_moves[self.index(segment)];
+            if self.fruit.contains(&segment) {
+                return first_move;
+            }
+            for &direction in DIRECTIONS.iter() {
+                if let Some(next) = self.neighbour(segment, direction) {
+                    let index = self.index(next);
+                    if !visited[index] {
+                        visited[index] = true;
+                        first_moves[index] = first_move;
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn index(&self, segment: Segment) -> usize {
+        segment.y as usize * self.width + segment.x as usize
+    }
+}
+
+/// Bot heading straight for the nearest fruit, avoiding only moves which end the game at once.
+#[derive(Clone, Debug, Default)]
+pub struct GreedyBot;
+
+impl Player for GreedyBot {
+    fn name(&self) -> &str {
+        "greedy"
+    }
+
+    fn next_direction(&mut self, game_logic: &GameLogic) -> Option<Direction> {
+        let grid = Grid::new(game_logic);
+        let distance_to_fruit = |field: Segment| grid.fruit().iter().map(|&fruit| grid.distance(field, fruit)).min();
+        let safe_moves = grid.safe_moves();
+        let keeps_direction = |direction: Direction| Some(direction) == grid.direction();
+        safe_moves.iter()
+            .min_by_key(|&&(direction, field)| (distance_to_fruit(field), !keeps_direction(direction)))
+            .map(|&(direction, _)| direction)
+    }
+}
+
+/// Bot following shortest paths to fruit, which avoids moves trapping it in an area smaller than
+/// its body.
+#[derive(Clone, Debug, Default)]
+pub struct BfsBot;
+
+impl Player for BfsBot {
+    fn name(&self) -> &str {
+        "bfs"
+    }
+
+    fn next_direction(&mut self, game_logic: &GameLogic) -> Option<Direction> {
+        let grid = Grid::new(game_logic);
+        let safe_moves = grid.safe_moves();
+        let area = |direction: Direction| safe_moves.iter()
+            .find(|&&(safe_direction, _)| safe_direction == direction)
+            .map_or(0, |&(_, field)| grid.reachable_area(field));
+        if let Some(direction) = grid.path_to_fruit() {
+            if area(direction) >= grid.length {
+                return Some(direction);
+            }
+        }
+        safe_moves.iter().map(|&(direction, _)| direction).max_by_key(|&direction| area(direction))
+    }
+}
+
+/// Closed path visiting every field of a board exactly once.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HamiltonianCycle {
+    width: usize,
+    height: usize,
+    order: Vec<Segment>,
+    positions: Vec<usize>,
+}
+
+impl HamiltonianCycle {
+    /// Builds a cycle over a `width` x `height` board. Returns `None` if there is none, i.e. if
+    /// both sides are odd or one of them is shorter than 2 fields.
+    pub fn new(width: usize, height: usize) -> Option<HamiltonianCycle> {
+        if width < 2 || height < 2 {
+            return None;
+        }
+        let order = if height % 2 == 0 {
+            HamiltonianCycle::by_rows(width, height, |x, y| Segment::new(x as i32, y as i32))
+        } else if width % 2 == 0 {
+            HamiltonianCycle::by_rows(height, width, |x, y| Segment::new(y as i32, x as i32))
+        } else {
+            return None;
+        };
+        let mut positions = vec![0; width * height];
+        for (position, segment) in order.iter().enumerate() {
+            positions[segment.y as usize * width + segment.x as usize] = position;
+        }
+        Some(HamiltonianCycle { width, height, order, positions })
+    }
+
+    // runs along the first row, zigzags through the remaining rows without the first column and
+    // returns through the first column, `height` must be even
+    fn by_rows<F>(width: usize, height: usize, segment: F) -> Vec<Segment>
+        where F: Fn(usize, usize) -> Segment {
+        let mut order: Vec<Segment> = (0..width).map(|x| segment(x, 0)).collect();
+        for y in 1..height {
+            if y % 2 == 1 {
+                order.extend((1..width).rev().map(|x| segment(x, y)));
+            } else {
+                order.extend((1..width).map(|x| segment(x, y)));
+            }
+        }
+        order.extend((1..height).rev().map(|y| segment(0, y)));
+        order
+    }
+
+    /// Returns the board width and height.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Returns the number of fields on the cycle.
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Returns true if the cycle has no fields, which never happens.
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Returns the field following a field on the cycle.
+    pub fn next(&self, segment: Segment) -> Segment {
+        self.order[(self.position(segment) + 1) % self.len()]
+    }
+
+    /// Returns the number of steps along the cycle from one field to another.
+    pub fn distance(&self, from: Segment, to: Segment) -> usize {
+        (self.position(to) + self.len() - self.position(from)) % self.len()
+    }
+
+    fn position(&self, segment: Segment) -> usize {
+        self.positions[segment.y as usize * self.width + segment.x as usize]
+    }
+}
+
+/// Bot following a Hamiltonian cycle of the board, which fills every board with an even side
+/// unless fruit keep growing it beyond the board. While the snake is short it takes shortcuts
+/// towards fruit which skip only free fields between its head and its tail on the cycle. On
+/// boards with walls or without a cycle it plays like `BfsBot`.
+#[derive(Clone, Debug, Default)]
+pub struct HamiltonianBot {
+    cycle: Option<HamiltonianCycle>,
+}
+
+impl Player for HamiltonianBot {
+    fn name(&self) -> &str {
+        "hamiltonian"
+    }
+
+    fn next_direction(&mut self, game_logic: &GameLogic) -> Option<Direction> {
+        let grid = Grid::new(game_logic);
+        if !grid.is_open() {
+            return BfsBot.next_direction(game_logic);
+        }
+        if self.cycle.as_ref().map(HamiltonianCycle::size) != Some(grid.size()) {
+            let (width, height) = grid.size();
+            self.cycle = HamiltonianCycle::new(width, height);
+        }
+        let cycle = match &self.cycle {
+            Some(cycle) => cycle,
+            None => return BfsBot.next_direction(game_logic),
+        };
+
+        let head = grid.head();
+        let to_tail = if grid.segments() > 1 { cycle.distance(head, grid.tail()) } else { cycle.len() };
+        let to_fruit = grid.fruit().iter().map(|&fruit| cycle.distance(head, fruit)).min().unwrap_or(1);
+        // free fields skipped by earlier shortcuts, they lie behind the head until the tail passes them
+        let holes = (cycle.len() - grid.segments()).saturating_sub(to_tail.saturating_sub(1));
+        let max_growth = game_logic.get_settings().max_fruit_points as usize;
+        let shortcut_allowed = |distance: usize| {
+            let holes = holes + distance - 1;
+            // the snake still fits between the head and the tail after the move, and skipped fields
+            // are passed by the tail before the snake can grow into them: the tail stands still
+            // while the snake grows, so it may eat on twice as many moves as it is long
+            distance + grid.length() + SHORTCUT_BUFFER <= to_tail &&
+                grid.length() + holes + 2 * max_growth * (grid.length() + holes) + SHORTCUT_BUFFER <= cycle.len()
+        };
+        grid.safe_moves().into_iter()
+            .map(|(direction, field)| (direction, cycle.distance(head, field)))
+            .filter(|&(_, distance)| distance == 1 || (distance <= to_fruit && shortcut_allowed(distance)))
+            .max_by_key(|&(_, distance)| distance)
+            .map(|(direction, _)| direction)
+            .or_else(|| BfsBot.next_direction(game_logic))
+    }
+}
+
+/// Built-in bots which can be chosen by name.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BotKind {
+    /// `GreedyBot`
+    Greedy,
+    /// `BfsBot`
+    Bfs,
+    /// `HamiltonianBot`
+    Hamiltonian,
+}
+
+impl BotKind {
+    /// All built-in bots.
+    pub const ALL: [BotKind; 3] = [BotKind::Greedy, BotKind::Bfs, BotKind::Hamiltonian];
+
+    /// Creates a bot of this kind.
+    pub fn create(&self) -> Box<dyn Player> {
+        match self {
+            BotKind::Greedy => Box::new(GreedyBot),
+            BotKind::Bfs => Box::new(BfsBot),
+            BotKind::Hamiltonian => Box::new(HamiltonianBot::default()),
+        }
+    }
+
+    /// Returns the name the bot is chosen by.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BotKind::Greedy => "greedy",
+            BotKind::Bfs => "bfs",
+            BotKind::Hamiltonian => "hamiltonian",
+        }
+    }
+}
+
+impl fmt::Display for BotKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for BotKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        BotKind::ALL.iter().copied().find(|kind| kind.name() == name).ok_or_else(|| {
+            let names: Vec<&str> = BotKind::ALL.iter().map(|kind| kind.name()).collect();
+            format!("unknown bot '{}', expected one of: {}", name, names.join(", "))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_logic::{GameSettings, GameStatus, WinCause};
+    use crate::headless::HeadlessRunner;
+    use crate::level::Level;
+
+    #[test]
+    fn given_fruit_next_to_snake_when_asked_then_bots_move_to_fruit() {
+        let level = Level::parse("snake2d-level 1\nsnake 1,1\nmap\n...\n..1\n...\n").unwrap();
+        let game_logic = GameLogic::from_level(&level, 0).with_settings(GameSettings::no_spawning());
+
+        assert_eq!(GreedyBot.next_direction(&game_logic), Some(Direction::Right));
+        assert_eq!(BfsBot.next_direction(&game_logic), Some(Direction::Right));
+    }
+
+    #[test]
+    fn given_wall_between_snake_and_fruit_when_asked_then_bfs_bot_goes_around() {
+        let level = Level::parse("snake2d-level 1\nsnake 0,1\nmap\n.....\n.#.1.\n.....\n").unwrap();
+        let mut game_logic = GameLogic::from_level(&level, 0).with_settings(GameSettings::no_spawning());
+        let mut bot = BfsBot;
+        for _ in 0..5 {
+            let direction = bot.next_direction(&game_logic);
+            game_logic.step(direction);
+        }
+
+        assert_eq!(game_logic.get_length(), 2);
+        assert!(!game_logic.get_status().is_over());
+    }
+
+    #[test]
+    fn given_snake_at_wall_when_asked_then_greedy_bot_does_not_hit_it() {
+        let level = Level::parse("snake2d-level 1\nsnake 2,0 1,0\ndirection right\nmap\n...\n...\n").unwrap();
+        let game_logic = GameLogic::from_level(&level, 0).with_settings(GameSettings::no_spawning());
+
+        assert_eq!(GreedyBot.next_direction(&game_logic), Some(Direction::Down));
+    }
+
+    #[test]
+    fn given_snake_crashed_into_border_when_snapshot_is_taken_then_head_off_board_is_skipped() {
+        let level = Level::parse("snake2d-level 1\nsnake 2,0 1,0\ndirection right\nmap\n...\n...\n").unwrap();
+        let mut game_logic = GameLogic::from_level(&level, 0).with_settings(GameSettings::no_spawning());
+        game_logic.step(None);
+        assert!(game_logic.get_status().is_over());
+        assert!(Grid::new(&game_logic).is_free(Segment::new(0, 1)));
+
+        let level = Level::parse("snake2d-level 1\nsnake 0,1 0,0\ndirection down\nmap\n...\n...\n").unwrap();
+        let mut game_logic = GameLogic::from_level(&level, 0).with_settings(GameSettings::no_spawning());
+        game_logic.step(None);
+        assert!(game_logic.get_status().is_over());
+        assert_eq!(Grid::new(&game_logic).safe_moves(), vec![]);
+        BfsBot.next_direction(&game_logic);
+    }
+
+    #[test]
+    fn given_toroidal_board_when_distance_is_measured_then_shorter_way_around_counts() {
+        let game_logic = GameLogic::with_seed(10, 10, Segment::new(0, 0), 0).with_topology(Topology::Toroidal);
+        let grid = Grid::new(&game_logic);
+
+        assert_eq!(grid.distance(Segment::new(0, 0), Segment::new(9, 8)), 3);
+        assert_eq!(grid.neighbour(Segment::new(0, 0), Direction::Up), Some(Segment::new(0, 9)));
+    }
+
+    #[test]
+    fn given_headless_games_when_bots_play_then_bfs_bot_grows_longer() {
+        let runner = HeadlessRunner::new(12, 12, Segment::new(2, 2)).max_ticks(3000);
+        let mean_length = |kind: BotKind| {
+            let summaries = runner.run_many(0..5, || {
+                let mut bot = kind.create();
+                move |game_logic: &GameLogic| bot.next_direction(game_logic)
+            });
+            summaries.iter().map(|summary| summary.length).sum::<usize>() / summaries.len()
+        };
+
+        let greedy = mean_length(BotKind::Greedy);
+        let bfs = mean_length(BotKind::Bfs);
+        assert!(greedy > 15, "greedy bot reached mean length {}", greedy);
+        assert!(bfs > 40, "bfs bot reached mean length {}", bfs);
+    }
+
+    #[test]
+    fn given_board_sizes_when_cycle_is_built_then_it_visits_every_field_once() {
+        for &(width, height) in [(2, 2), (4, 3), (3, 4), (6, 6), (7, 2)].iter() {
+            let cycle = HamiltonianCycle::new(width, height).unwrap();
+            assert_eq!(cycle.len(), width * height);
+            let mut segment = Segment::new(0, 0);
+            let mut visited = std::collections::HashSet::new();
+            for _ in 0..cycle.len() {
+                let next = cycle.next(segment);
+                assert_eq!((next.x - segment.x).abs() + (next.y - segment.y).abs(), 1);
+                assert!(visited.insert(next));
+                segment = next;
+            }
+            assert_eq!(segment, Segment::new(0, 0));
+            assert_eq!(cycle.distance(Segment::new(0, 0), cycle.next(Segment::new(0, 0))), 1);
+        }
+        assert_eq!(HamiltonianCycle::new(5, 5), None);
+        assert_eq!(HamiltonianCycle::new(1, 4), None);
+    }
+
+    #[test]
+    fn given_even_boards_when_hamiltonian_bot_plays_then_snake_fills_the_board() {
+        for &(width, height, topology) in [(6, 6, Topology::Bounded), (8, 5, Topology::Bounded),
+                (6, 4, Topology::Toroidal)].iter() {
+            let runner = HeadlessRunner::new(width, height, Segment::new(1, 1)).topology(topology).max_ticks(20_000);
+            for seed in 0..3 {
+                let mut bot = HamiltonianBot::default();
+                let summary = runner.run(seed, |game_logic| bot.next_direction(game_logic));
+                assert_eq!(summary.status, GameStatus::Won { cause: WinCause::BoardFilled, score: summary.score },
+                           "seed {} on {}x{}", seed, width, height);
+                assert_eq!(summary.length, width * height, "seed {} on {}x{}", seed, width, height);
+            }
+        }
+    }
+
+    #[test]
+    fn given_odd_board_when_hamiltonian_bot_plays_then_it_falls_back_to_bfs() {
+        let runner = HeadlessRunner::new(5, 5, Segment::new(1, 1)).max_ticks(200);
+        let mut bot = HamiltonianBot::default();
+        let summary = runner.run(1, |game_logic| bot.next_direction(game_logic));
+        assert!(summary.length > 1);
+    }
+
+    #[test]
+    fn given_bot_names_when_parsed_then_kinds_are_found() {
+        assert_eq!("bfs".parse::<BotKind>(), Ok(BotKind::Bfs));
+        assert_eq!(BotKind::Greedy.to_string(), "greedy");
+        assert!("clever".parse::<BotKind>().is_err());
+    }
+}
@@ -0,0 +1,447 @@
+//! Command line options shared by the game frontends.
+
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::bot::BotKind;
+use crate::config::Config;
+use crate::game_controller::GameController;
+use crate::game_logic::GameLogic;
+use crate::headless::DEFAULT_MAX_TICKS;
+use crate::high_scores::HighScores;
+use crate::level::Level;
+use crate::replay::{Replay, ReplayPlayer};
+use crate::tournament::{self, ReportFormat, Tournament};
+use crate::{Segment, Topology};
+
+/// Command line options of the game. Options which are given override the configuration file.
+#[derive(Parser, Clone, Debug, PartialEq)]
+#[command(name = "snake2d", version, about = "A snake2d game.")]
+pub struct Options {
+    /// Configuration file [default: snake2d/config.toml in the user's configuration directory]
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    /// Print the high-score table and exit
+    #[arg(long)]
+    pub scores: bool,
+    /// Name recorded in the high-score table [default: the user's name]
+    #[arg(long, value_name = "NAME")]
+    pub player: Option<String>,
+    /// Replay file to watch instead of playing
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["record", "level", "wrap", "width", "height", "start", "seed", "bot"])]
+    pub replay: Option<PathBuf>,
+    /// File where the played game is recorded
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+    /// Let a bot play instead of the keyboard: greedy, bfs or hamiltonian. Bot games are not
+    /// recorded in the high-score table
+    #[arg(long, value_name = "BOT")]
+    pub bot: Option<BotKind>,
+    /// Level file to play instead of an empty board
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["width", "height", "start"])]
+    pub level: Option<PathBuf>,
+    /// Play on a board whose opposite edges are connected
+    #[arg(long)]
+    pub wrap: bool,
+    /// Board width in fields [default: 25]
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    pub width: Option<u16>,
+    /// Board height in fields [default: 25]
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    pub height: Option<u16>,
+    /// Field where the snake starts [default: 5,6]
+    #[arg(long, value_name = "X,Y", value_parser = parse_segment)]
+    pub start: Option<Segment>,
+    /// Game steps per second [default: 15]
+    #[arg(long, value_name = "TICKS", value_parser = clap::value_parser!(u64).range(1..=1000))]
+    pub tick_rate: Option<u64>,
+    /// Comma separated lengths or scores at which the game speeds up [default: constant speed]
+    #[arg(long, value_name = "LENGTHS", value_delimiter = ',')]
+    pub speed_thresholds: Vec<usize>,
+    /// Window size in pixels [default: 480x480]
+    #[arg(long, value_name = "WIDTHxHEIGHT", value_parser = parse_size)]
+    pub window_size: Option<(u32, u32)>,
+    /// Seed of the fruit generator, random if not given
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Folder with fruit textures, searched for around the working directory [default: assets]
+    #[arg(long, value_name = "FOLDER")]
+    pub assets: Option<String>,
+    /// Runs a command instead of the game
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Commands run instead of the game.
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+pub enum Command {
+    /// Play headless games of bots and report their statistics
+    Tournament(TournamentOptions),
+}
+
+/// Options of the `tournament` command. The games are played with the configured rules and
+/// topology.
+#[derive(Args, Clone, Debug, PartialEq)]
+pub struct TournamentOptions {
+    /// Bots taking part, separated by commas [default: all bots]
+    #[arg(long, value_name = "BOTS", value_delimiter = ',')]
+    pub bots: Vec<BotKind>,
+    /// Games played by every bot on every board
+    #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u64).range(1..))]
+    pub games: u64,
+    /// Seed of the first game, the following games are played with the next seeds, wrapping
+    /// around to 0
+    #[arg(long, value_name = "SEED", default_value_t = 0)]
+    pub first_seed: u64,
+    /// Board sizes separated by commas, the snake starts in the middle [default: the configured
+    /// board or level]
+    #[arg(long, value_name = "WIDTHxHEIGHT", value_delimiter = ',', value_parser = parse_size)]
+    pub sizes: Vec<(u32, u32)>,
+    /// Ticks after which a game which has not ended is stopped
+    #[arg(long, value_name = "TICKS", default_value_t = DEFAULT_MAX_TICKS)]
+    pub max_ticks: usize,
+    /// Format of the report: table, json or csv
+    #[arg(long, default_value_t = ReportFormat::Table)]
+    pub format: ReportFormat,
+}
+
+impl TournamentOptions {
+    /// Creates the tournament. Games are played in `level`, or on empty boards of the given sizes
+    /// with the topology of `level`.
+    pub fn tournament(&self, level: &Level, config: &Config) -> Tournament {
+        let bots = if self.bots.is_empty() { BotKind::ALL.to_vec() } else { self.bots.clone() };
+        let levels = if self.sizes.is_empty() {
+            vec![level.clone()]
+        } else {
+            self.sizes.iter().map(|&(width, height)| {
+                let start = Segment::new(width as i32 / 2, height as i32 / 2);
+                Level { topology: level.topology, ..Level::empty(width as usize, height as usize, start) }
+            }).collect()
+        };
+        // the seeds wrap around after the largest one instead of overflowing
+        let seeds = (0..self.games).map(|game| self.first_seed.wrapping_add(game)).collect();
+        Tournament::new(bots, levels, seeds)
+            .settings(config.game.clone())
+            .max_ticks(self.max_ticks)
+    }
+}
+
+impl Options {
+    /// Parses the options of the running program and loads the configuration they point to,
+    /// exiting with a usage message if either is invalid.
+    pub fn from_args() -> (Options, Config) {
+        let options = Options::parse();
+        match options.load_config() {
+            Ok(config) => (options, config),
+            Err(message) => {
+                use clap::CommandFactory;
+                Options::command().error(clap::error::ErrorKind::ValueValidation, message).exit()
+            },
+        }
+    }
+
+    /// Loads the configuration file and overrides it with the options.
+    pub fn load_config(&self) -> Result<Config, String> {
+        let mut config = match (&self.config, Config::default_path()) {
+            (Some(path), _) => Config::load(path),
+            (None, Some(path)) => Config::load_or_default(path),
+            (None, None) => Ok(Config::default()),
+        }.map_err(|error| error.to_string())?;
+        self.apply_to(&mut config);
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Overrides the configuration with the options which are given.
+    pub fn apply_to(&self, config: &mut Config) {
+        if let Some(width) = self.width {
+            config.board.width = width as usize;
+        }
+        if let Some(height) = self.height {
+            config.board.height = height as usize;
+        }
+        if let Some(start) = self.start {
+            config.board.start = [start.x, start.y];
+        }
+        config.board.wrap |= self.wrap;
+        if let Some(tick_rate) = self.tick_rate {
+            config.game.tick_rate = tick_rate;
+        }
+        if !self.speed_thresholds.is_empty() {
+            config.game.speed.thresholds = self.speed_thresholds.clone();
+        }
+        if let Some((width, height)) = self.window_size {
+            config.window.size = [width, height];
+        }
+        if let Some(assets) = &self.assets {
+            config.window.assets = assets.clone();
+        }
+        if let Some(player) = &self.player {
+            config.scores.player_name = player.clone();
+        }
+    }
+
+    /// Returns the level to play: the level file if given, otherwise an empty board described by
+    /// the configuration.
+    pub fn load_level(&self, config: &Config) -> Result<Level, String> {
+        let mut level = match &self.level {
+            Some(path) => Level::load(path).map_err(|error| format!("Could not load level {}: {}", path.display(), error))?,
+            None => config.board.level(),
+        };
+        if config.board.wrap {
+            level.topology = Topology::Toroidal;
+        }
+        level.validate()?;
+        Ok(level)
+    }
+
+    /// Returns the mode recorded in the high-score table: the level name or `classic`, followed
+    /// by `wrap` when playing on a toroidal board.
+    pub fn mode(&self, config: &Config) -> String {
+        let level_name = self.level.as_ref()
+            .and_then(|path| path.file_stem())
+            .map(|stem| stem.to_string_lossy().into_owned());
+        match (level_name, config.board.wrap) {
+            (Some(name), true) => format!("{}+wrap", name),
+            (Some(name), false) => name,
+            (None, true) => String::from("wrap"),
+            (None, false) => String::from("classic"),
+        }
+    }
+
+    /// Returns the seed of the game, drawing a random one if none was given.
+    pub fn seed(&self) -> u64 {
+        self.seed.unwrap_or_else(rand::random)
+    }
+
+    /// Runs the command given instead of the game, printing the high-score table for `--scores`
+    /// or the report of a tournament. Returns false if the game is to be played.
+    pub fn run_command(&self, config: &Config) -> Result<bool, String> {
+        if self.scores {
+            let high_scores = HighScores::load_default(config.scores.max_entries)?;
+            high_scores.table_lines().iter().for_each(|line| println!("{}", line));
+            return Ok(true);
+        }
+        match &self.command {
+            Some(Command::Tournament(tournament_options)) => {
+                let statistics = tournament_options.tournament(&self.load_level(config)?, config).run();
+                print!("{}", tournament::report(&statistics, tournament_options.format));
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+
+    /// Creates the controller of the game to play, or of the replay to watch.
+    pub fn game_controller(&self, config: &Config) -> Result<GameController, String> {
+        let controller = match &self.replay {
+            Some(path) => {
+                let replay = Replay::load(path)
+                    .map_err(|error| format!("Could not load replay {}: {}", path.display(), error))?;
+                GameController::with_replay(ReplayPlayer::new(replay))
+            },
+            None => {
+                let game_logic = GameLogic::from_level(&self.load_level(config)?, self.seed())
+                    .with_settings(config.game.clone());
+                GameController::new(game_logic)
+            },
+        };
+        let mut controller = controller.with_key_bindings(config.keys.bindings()?);
+        if let Some(bot) = self.bot {
+            controller = controller.with_player(bot.create());
+        }
+        if self.record.is_some() {
+            controller.start_recording();
+        }
+        Ok(controller)
+    }
+
+    /// Returns true if finished games are recorded in the high-score table, which bots and
+    /// replays are not.
+    pub fn records_high_scores(&self) -> bool {
+        self.replay.is_none() && self.bot.is_none()
+    }
+
+    /// Saves the game recorded by the controller to the file given with `--record`.
+    pub fn save_recording(&self, controller: &GameController) {
+        if let (Some(path), Some(recorder)) = (&self.record, controller.recorder()) {
+            match recorder.replay().save(path) {
+                Ok(()) => println!("Game recorded to {}", path.display()),
+                Err(error) => eprintln!("Could not save replay {}: {}", path.display(), error),
+            }
+        }
+    }
+}
+
+// parses "x,y"
+fn parse_segment(value: &str) -> Result<Segment, String> {
+    let (x, y) = value.split_once(',').ok_or("expected X,Y")?;
+    let coordinate = |value: &str| value.trim().parse::<i32>().map_err(|error| error.to_string());
+    Ok(Segment::new(coordinate(x)?, coordinate(y)?))
+}
+
+// parses "widthxheight"
+fn parse_size(value: &str) -> Result<(u32, u32), String> {
+    let (width, height) = value.split_once('x').ok_or("expected WIDTHxHEIGHT")?;
+    let dimension = |value: &str| match value.trim().parse::<u32>() {
+        Ok(0) => Err(String::from("size must be positive")),
+        Ok(dimension) => Ok(dimension),
+        Err(error) => Err(error.to_string()),
+    };
+    Ok((dimension(width)?, dimension(height)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, clap::Error> {
+        Options::try_parse_from(std::iter::once("snake2d").chain(args.iter().copied()))
+    }
+
+    // applies the options to the default configuration, ignoring the user's configuration file
+    fn config(options: &Options) -> Result<Config, String> {
+        let mut config = Config::default();
+        options.apply_to(&mut config);
+        config.validate().map(|()| config)
+    }
+
+    #[test]
+    fn given_no_arguments_then_configuration_is_not_changed() {
+        let options = parse(&[]).unwrap();
+        let config = config(&options).unwrap();
+
+        assert_eq!(config, Config::default());
+        assert_eq!(options.seed, None);
+        assert_eq!(options.load_level(&config).unwrap(), Level::empty(25, 25, Segment::new(5, 6)));
+    }
+
+    #[test]
+    fn given_arguments_then_configuration_is_overridden() {
+        let options = parse(&["--width", "40", "--height", "30", "--start", "39,0", "--tick-rate", "20",
+                              "--window-size", "800x600", "--seed", "7", "--assets", "textures", "--wrap",
+                              "--speed-thresholds", "5,10,20"]).unwrap();
+        let config = config(&options).unwrap();
+
+        assert_eq!((config.board.width, config.board.height), (40, 30));
+        assert_eq!(config.board.start, [39, 0]);
+        assert_eq!(config.game.tick_rate, 20);
+        assert_eq!(config.game.speed.thresholds, vec![5, 10, 20]);
+        assert_eq!(config.window.size, [800, 600]);
+        assert_eq!(config.window.assets, "textures");
+        assert_eq!(options.seed(), 7);
+        assert_eq!(parse(&["--bot", "bfs"]).unwrap().bot, Some(BotKind::Bfs));
+        assert_eq!(options.mode(&config), "wrap");
+        assert_eq!(options.load_level(&config).unwrap().topology, Topology::Toroidal);
+    }
+
+    #[test]
+    fn given_configuration_file_then_arguments_override_only_given_values() {
+        let path = std::env::temp_dir().join(format!("snake2d-cli-test-{}.toml", std::process::id()));
+        std::fs::write(&path, "[board]\nwidth = 10\nheight = 10\n[game]\ntick_rate = 30\n").unwrap();
+        let options = parse(&["--config", path.to_str().unwrap(), "--height", "12"]).unwrap();
+        let config = options.load_config();
+        std::fs::remove_file(&path).unwrap();
+        let config = config.unwrap();
+
+        assert_eq!((config.board.width, config.board.height), (10, 12));
+        assert_eq!(config.game.tick_rate, 30);
+    }
+
+    #[test]
+    fn given_level_and_player_then_mode_and_player_are_set() {
+        let options = parse(&["--level", "levels/arena.txt", "--player", "marcin"]).unwrap();
+        let config = config(&options).unwrap();
+
+        assert_eq!(options.mode(&config), "arena");
+        assert_eq!(config.scores.player(), "marcin");
+        assert_eq!(options.mode(&Config::default()), "arena");
+        assert_eq!(parse(&[]).unwrap().mode(&Config::default()), "classic");
+    }
+
+    #[test]
+    fn given_start_outside_of_board_then_validation_fails() {
+        assert!(config(&parse(&["--start", "25,0"]).unwrap()).is_err());
+        assert!(config(&parse(&["--start", "0,-1"]).unwrap()).is_err());
+        assert!(config(&parse(&["--width", "5", "--height", "5", "--start", "4,5"]).unwrap()).is_err());
+        assert!(config(&parse(&["--width", "5", "--height", "5", "--start", "4,4"]).unwrap()).is_ok());
+        // the default start field lies outside of a small board
+        assert!(config(&parse(&["--width", "5"]).unwrap()).is_err());
+    }
+
+    #[test]
+    fn given_invalid_values_then_parsing_fails() {
+        assert!(parse(&["--width", "0"]).is_err());
+        assert!(parse(&["--tick-rate", "0"]).is_err());
+        assert!(parse(&["--start", "5"]).is_err());
+        assert!(parse(&["--window-size", "480"]).is_err());
+        assert!(parse(&["--window-size", "0x480"]).is_err());
+        assert!(parse(&["--level", "levels/arena.txt", "--width", "10"]).is_err());
+        assert!(parse(&["--replay", "game.replay", "--seed", "1"]).is_err());
+        assert!(parse(&["--bot", "clever"]).is_err());
+        assert!(parse(&["--replay", "game.replay", "--bot", "bfs"]).is_err());
+    }
+
+    #[test]
+    fn given_tournament_command_then_tournament_plays_given_bots_and_boards() {
+        let options = parse(&["--wrap", "tournament", "--bots", "greedy,bfs", "--games", "2", "--first-seed", "5",
+                              "--sizes", "6x4,8x8", "--max-ticks", "300", "--format", "csv"]).unwrap();
+        let config = config(&options).unwrap();
+        let tournament_options = match &options.command {
+            Some(Command::Tournament(tournament_options)) => tournament_options,
+            command => panic!("Expected tournament command, got {:?}", command),
+        };
+        assert_eq!(tournament_options.bots, vec![BotKind::Greedy, BotKind::Bfs]);
+        assert_eq!(tournament_options.format, ReportFormat::Csv);
+
+        let statistics = tournament_options.tournament(&options.load_level(&config).unwrap(), &config).run();
+        let rows: Vec<(&str, usize, usize, usize)> = statistics.iter()
+            .map(|row| (row.bot.as_str(), row.width, row.height, row.games))
+            .collect();
+        assert_eq!(rows, vec![("greedy", 6, 4, 2), ("greedy", 8, 8, 2), ("bfs", 6, 4, 2), ("bfs", 8, 8, 2)]);
+        assert!(statistics.iter().all(|row| row.wall_deaths == 0 && row.max_ticks <= 300));
+
+        let defaults = parse(&["tournament"]).unwrap();
+        assert_eq!(defaults.command, Some(Command::Tournament(TournamentOptions {
+            bots: vec![], games: 20, first_seed: 0, sizes: vec![], max_ticks: DEFAULT_MAX_TICKS,
+            format: ReportFormat::Table,
+        })));
+        assert!(parse(&["tournament", "--games", "0"]).is_err());
+        assert!(parse(&["tournament", "--format", "xml"]).is_err());
+    }
+
+    #[test]
+    fn given_first_seed_near_maximum_then_tournament_seeds_wrap_around() {
+        let options = parse(&["tournament", "--bots", "greedy", "--games", "3", "--first-seed", &u64::MAX.to_string(),
+                              "--sizes", "4x4", "--max-ticks", "10"]).unwrap();
+        let config = config(&options).unwrap();
+        let tournament_options = match &options.command {
+            Some(Command::Tournament(tournament_options)) => tournament_options,
+            command => panic!("Expected tournament command, got {:?}", command),
+        };
+
+        let statistics = tournament_options.tournament(&options.load_level(&config).unwrap(), &config).run();
+        assert_eq!(statistics.iter().map(|row| row.games).collect::<Vec<_>>(), vec![3]);
+    }
+
+    #[test]
+    fn given_game_options_then_controller_plays_the_described_game() {
+        let options = parse(&["--seed", "7", "--bot", "bfs", "--record", "game.replay", "--width", "10"]).unwrap();
+        let controller = options.game_controller(&config(&options).unwrap()).unwrap();
+
+        assert_eq!(controller.game_logic.get_seed(), 7);
+        assert_eq!(controller.game_logic.get_board_size(), (10, 25));
+        assert_eq!(controller.player().map(|player| player.name()), Some("bfs"));
+        assert!(controller.recorder().is_some());
+        assert!(!options.records_high_scores());
+        assert!(parse(&[]).unwrap().records_high_scores());
+        assert!(parse(&["--replay", "no-such.replay"]).unwrap().game_controller(&Config::default()).is_err());
+    }
+
+    #[test]
+    fn given_missing_configuration_file_then_loading_fails() {
+        let options = parse(&["--config", "no-such-config.toml"]).unwrap();
+        assert!(options.load_config().is_err());
+    }
+}
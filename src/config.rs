@@ -0,0 +1,485 @@
+//! Persistent configuration.
+//!
+//! The configuration is a TOML file, by default `snake2d/config.toml` in the user's configuration
+//! directory (e.g. `~/.config` on Linux). Every section and value is optional, missing ones keep
+//! their defaults. The example below also turns on the speed-up, which is off by default:
+//!
+//! ```toml
+//! [board]
+//! width = 25
+//! height = 25
+//! start = [5, 6]
+//! wrap = false
+//!
+//! [window]
+//! size = [480, 480]
+//! fps = 60
+//! assets = "assets"
+//!
+//! [game]
+//! tick_rate = 15
+//! max_fruit = 5
+//! max_spawned_fruit = 2
+//! max_fruit_points = 3
+//! spawn_on_start = true
+//! spawn_on_growth = true
+//! target_length = 100
+//!
+//! [game.score]
+//! fruit_scores = [10, 20, 30]
+//! combo_ticks = 0
+//! max_combo = 5
+//! speed_multiplier = false
+//!
+//! [game.speed]
+//! metric = "length"
+//! thresholds = [5, 10, 15, 20, 30, 40, 50]
+//! step = 2
+//! max_tick_rate = 30
+//!
+//! [keys]
+//! preset = "wasd"
+//! pause = ["Space"]
+//! quit = ["X"]
+//!
+//! [scores]
+//! player_name = "marcin"
+//! max_entries = 10
+//!
+//! [view]
+//! background_color = [1.0, 1.0, 1.0, 1.0]
+//! snake_head_color = [0.9, 0.0, 0.0, 1.0]
+//!
+//! [view.fruit_textures]
+//! 1 = "apple"
+//! 2 = "banana"
+//! 3 = "cherry"
+//! ```
+//!
+//! `preset` of the key bindings is `arrows`, `wasd` or `vim`; keys listed for an action (`move_up`,
+//! `move_down`, `move_left`, `move_right`, `pause`, `step`, `restart`, `quit`) are bound to it in
+//! addition to the keys of the preset, see [`crate::key_bindings`] for key names. A game is won
+//! when the snake fills the board or reaches `target_length` or `target_score`, both unset by
+//! default. `fruit_textures` maps points of a fruit to the name of its texture in the asset
+//! folder and must name a texture for every value up to `max_fruit_points`; fruit without a
+//! texture, e.g. placed by a level, is drawn as a square of `fruit_color`. An empty `player_name`
+//! stands for the name of the user running the game.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use graphics::types::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::game_logic::GameSettings;
+use crate::game_view::GameViewSettings;
+use crate::high_scores;
+use crate::key_bindings::{Action, KeyBindings, KeyPreset};
+use crate::level::Level;
+use crate::{Direction, Segment};
+
+/// Name of the configuration file in the user's configuration directory.
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Error returned when the configuration cannot be loaded.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// Reading the configuration file has failed.
+    Io(io::Error),
+    /// Configuration file is malformed or contains invalid values.
+    Format {
+        /// Line (counted from 1) where the problem was found, 0 if the problem concerns the
+        /// configuration as a whole.
+        line: usize,
+        /// Description of the problem.
+        message: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(error) => write!(f, "configuration I/O error: {}", error),
+            ConfigError::Format { line: 0, message } => write!(f, "invalid configuration: {}", message),
+            ConfigError::Format { line, message } => write!(f, "malformed configuration at line {}: {}", line, message),
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConfigError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(error: io::Error) -> Self {
+        ConfigError::Io(error)
+    }
+}
+
+/// Board of games started without a level file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BoardConfig {
+    /// Board width in fields.
+    pub width: usize,
+    /// Board height in fields.
+    pub height: usize,
+    /// Field where the snake starts.
+    pub start: [i32; 2],
+    /// Connect opposite edges of the board.
+    pub wrap: bool,
+}
+
+impl Default for BoardConfig {
+    fn default() -> Self {
+        BoardConfig {
+            width: 25,
+            height: 25,
+            start: [5, 6],
+            wrap: false,
+        }
+    }
+}
+
+impl BoardConfig {
+    /// Returns an empty level of this size, with a single segment snake at the start field.
+    pub fn level(&self) -> Level {
+        Level::empty(self.width, self.height, Segment::new(self.start[0], self.start[1]))
+    }
+}
+
+/// Window of the game.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    /// Window size in pixels.
+    pub size: [u32; 2],
+    /// Maximum number of frames drawn per second, independent of the tick rate of the game.
+    pub fps: u64,
+    /// Folder with fruit textures.
+    pub assets: String,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            size: [480, 480],
+            fps: 60,
+            assets: String::from("assets"),
+        }
+    }
+}
+
+/// Keys controlling the game.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeysConfig {
+    /// Bindings the listed keys are added to.
+    pub preset: KeyPreset,
+    /// Keys turning the snake up.
+    pub move_up: Vec<String>,
+    /// Keys turning the snake down.
+    pub move_down: Vec<String>,
+    /// Keys turning the snake left.
+    pub move_left: Vec<String>,
+    /// Keys turning the snake right.
+    pub move_right: Vec<String>,
+    /// Keys pausing and resuming the game.
+    pub pause: Vec<String>,
+    /// Keys advancing a paused game by a single tick.
+    pub step: Vec<String>,
+    /// Keys starting a new game.
+    pub restart: Vec<String>,
+    /// Keys closing the game.
+    pub quit: Vec<String>,
+}
+
+impl KeysConfig {
+    /// Creates the key bindings of the preset extended with the listed keys.
+    pub fn bindings(&self) -> Result<KeyBindings, String> {
+        let mut bindings = KeyBindings::preset(self.preset);
+        let actions = [
+            (&self.move_up, Action::Move(Direction::Up)),
+            (&self.move_down, Action::Move(Direction::Down)),
+            (&self.move_left, Action::Move(Direction::Left)),
+            (&self.move_right, Action::Move(Direction::Right)),
+            (&self.pause, Action::Pause),
+            (&self.step, Action::Step),
+            (&self.restart, Action::Restart),
+            (&self.quit, Action::Quit),
+        ];
+        for (keys, action) in actions.iter() {
+            for key in keys.iter() {
+                bindings.bind(key, *action)?;
+            }
+        }
+        Ok(bindings)
+    }
+}
+
+/// High-score table.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScoresConfig {
+    /// Name recorded with finished games, the user's name if empty.
+    pub player_name: String,
+    /// Number of games kept in the table.
+    pub max_entries: usize,
+}
+
+impl Default for ScoresConfig {
+    fn default() -> Self {
+        ScoresConfig {
+            player_name: String::new(),
+            max_entries: high_scores::DEFAULT_CAPACITY,
+        }
+    }
+}
+
+impl ScoresConfig {
+    /// Returns the name recorded with finished games.
+    pub fn player(&self) -> String {
+        if self.player_name.is_empty() {
+            high_scores::default_player_name()
+        } else {
+            self.player_name.clone()
+        }
+    }
+}
+
+/// Colors and textures of the game view.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ViewConfig {
+    /// Background color
+    pub background_color: Color,
+    /// Snake body color
+    pub snake_body_color: Color,
+    /// Snake head color
+    pub snake_head_color: Color,
+    /// Wall color
+    pub wall_color: Color,
+    /// Color of fruit without a texture
+    pub fruit_color: Color,
+    /// Color of the overlay covering the board once the game is over
+    pub game_over_color: Color,
+    /// Color of the overlay covering the board once the game is won
+    pub game_won_color: Color,
+    /// Texture names of fruit, keyed by their points
+    pub fruit_textures: BTreeMap<String, String>,
+}
+
+impl Default for ViewConfig {
+    fn default() -> Self {
+        let settings = GameViewSettings::new();
+        ViewConfig {
+            background_color: settings.background_color,
+            snake_body_color: settings.snake_body_color,
+            snake_head_color: settings.snake_head_color,
+            wall_color: settings.wall_color,
+            fruit_color: settings.fruit_color,
+            game_over_color: settings.game_over_color,
+            game_won_color: settings.game_won_color,
+            fruit_textures: settings.scores_lookup.iter()
+                .map(|(points, name)| (points.to_string(), name.clone()))
+                .collect(),
+        }
+    }
+}
+
+impl ViewConfig {
+    /// Creates game view settings with these colors and textures.
+    pub fn settings(&self) -> Result<GameViewSettings, String> {
+        let mut scores_lookup = std::collections::HashMap::new();
+        for (points, name) in self.fruit_textures.iter() {
+            let points = points.parse::<u8>()
+                .map_err(|_| format!("fruit points '{}' are not a number between 0 and 255", points))?;
+            scores_lookup.insert(points, name.clone());
+        }
+        Ok(GameViewSettings {
+            background_color: self.background_color,
+            snake_body_color: self.snake_body_color,
+            snake_head_color: self.snake_head_color,
+            wall_color: self.wall_color,
+            fruit_color: self.fruit_color,
+            game_over_color: self.game_over_color,
+            game_won_color: self.game_won_color,
+            scores_lookup,
+        })
+    }
+}
+
+/// Settings of the game read from the configuration file.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Board of games started without a level file.
+    pub board: BoardConfig,
+    /// Window of the game.
+    pub window: WindowConfig,
+    /// Gameplay rules.
+    pub game: GameSettings,
+    /// Keys controlling the game.
+    pub keys: KeysConfig,
+    /// High-score table.
+    pub scores: ScoresConfig,
+    /// Colors and textures of the game view.
+    pub view: ViewConfig,
+}
+
+impl Config {
+    /// Returns the path of the configuration file in the user's configuration directory.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|directory| directory.join("snake2d").join(CONFIG_FILE_NAME))
+    }
+
+    /// Loads the configuration from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
+        Config::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Loads the configuration from a file, or returns the default one if the file does not exist.
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Config::parse(&text),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(error) => Err(ConfigError::Io(error)),
+        }
+    }
+
+    /// Parses and validates the configuration.
+    pub fn parse(text: &str) -> Result<Config, ConfigError> {
+        let config: Config = toml::from_str(text).map_err(|error| {
+            let line = error.span().map_or(0, |span| text[..span.start].matches('\n').count() + 1);
+            ConfigError::Format { line, message: String::from(error.message()) }
+        })?;
+        config.validate().map_err(|message| ConfigError::Format { line: 0, message })?;
+        Ok(config)
+    }
+
+    /// Checks values which cannot be checked while parsing, e.g. that the snake starts on the board.
+    pub fn validate(&self) -> Result<(), String> {
+        self.board.level().validate().map_err(|message| format!("invalid board: {}", message))?;
+        if self.window.size.contains(&0) {
+            return Err(String::from("window size must be positive"));
+        }
+        if self.window.fps == 0 {
+            return Err(String::from("'fps' must be positive"));
+        }
+        self.game.validate()?;
+        self.keys.bindings().map_err(|message| format!("invalid key bindings: {}", message))?;
+        if self.scores.max_entries == 0 {
+            return Err(String::from("'max_entries' must be positive"));
+        }
+        let view_settings = self.view.settings()?;
+        let missing_texture = (1..=self.game.max_fruit_points).find(|&points| view_settings.fruit_name(points).is_none());
+        if let Some(points) = missing_texture {
+            return Err(format!("no fruit texture for fruit worth {} points", points));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_empty_file_when_parsed_then_defaults_are_used() {
+        let config = Config::parse("").unwrap();
+
+        assert_eq!(config, Config::default());
+        assert_eq!(config.game, GameSettings::default());
+        assert_eq!(config.board.level(), Level::empty(25, 25, Segment::new(5, 6)));
+        assert_eq!(config.view.settings().unwrap().fruit_name(2), Some("banana"));
+    }
+
+    #[test]
+    fn given_partial_file_when_parsed_then_missing_values_are_defaults() {
+        let text = "[board]\nwidth = 40\nstart = [30, 1]\n\n\
+                    [game]\nmax_fruit = 1\nmax_fruit_points = 1\nspawn_on_growth = false\ntarget_score = 500\n\n\
+                    [view]\nwall_color = [0.0, 0.5, 0.0, 1.0]\n\n[view.fruit_textures]\n1 = \"melon\"\n";
+        let config = Config::parse(text).unwrap();
+
+        assert_eq!((config.board.width, config.board.height), (40, 25));
+        assert_eq!(config.board.start, [30, 1]);
+        assert_eq!(config.game, GameSettings { max_fruit: 1, max_fruit_points: 1, spawn_on_growth: false,
+            target_score: Some(500), ..GameSettings::default() });
+        let view_settings = config.view.settings().unwrap();
+        assert_eq!(view_settings.wall_color, [0.0, 0.5, 0.0, 1.0]);
+        assert_eq!(view_settings.fruit_name(1), Some("melon"));
+        assert_eq!(view_settings.fruit_name(2), None);
+        assert_eq!(config.window, WindowConfig::default());
+    }
+
+    #[test]
+    fn given_default_config_when_written_and_parsed_then_config_is_the_same() {
+        let text = toml::to_string(&Config::default()).unwrap();
+        assert_eq!(Config::parse(&text).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn given_malformed_file_when_parsed_then_error_points_to_line() {
+        match Config::parse("[board]\nwidth = 10\nheight = \"ten\"\n") {
+            Err(ConfigError::Format { line: 3, .. }) => (),
+            other => panic!("Expected format error at line 3, got {:?}", other),
+        }
+        match Config::parse("[board]\ncolor = 1\n") {
+            Err(ConfigError::Format { line: 2, .. }) => (),
+            other => panic!("Expected format error at line 2, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn given_invalid_values_when_parsed_then_error_is_returned() {
+        let invalid = [
+            "[board]\nstart = [25, 0]\n",
+            "[board]\nwidth = 0\n",
+            "[game]\ntick_rate = 0\n",
+            "[window]\nsize = [0, 480]\n",
+            "[window]\nfps = 0\n",
+            "[game]\nmax_fruit_points = 0\n",
+            "[scores]\nmax_entries = 0\n",
+            "[view.fruit_textures]\napple = \"apple\"\n",
+            "[game]\ntarget_length = 0\n",
+            "[view.fruit_textures]\n7 = \"melon\"\n",
+            "[keys]\npause = [\"F1\"]\n",
+            "[keys]\nquit = [\"up\"]\n",
+        ];
+        for text in invalid.iter() {
+            match Config::parse(text) {
+                Err(ConfigError::Format { line: 0, .. }) => (),
+                other => panic!("Expected invalid configuration error for {:?}, got {:?}", text, other),
+            }
+        }
+    }
+
+    #[test]
+    fn given_keys_section_when_parsed_then_keys_extend_preset() {
+        let config = Config::parse("[keys]\npreset = \"vim\"\npause = [\"space\", \"P\"]\nmove_up = [\"I\"]\n").unwrap();
+        let bindings = config.keys.bindings().unwrap();
+
+        assert_eq!(bindings.action("k"), Some(Action::Move(Direction::Up)));
+        assert_eq!(bindings.action("i"), Some(Action::Move(Direction::Up)));
+        assert_eq!(bindings.action("Up"), Some(Action::Move(Direction::Up)));
+        assert_eq!(bindings.describe(Action::Pause), "P/Space");
+        assert_eq!(KeysConfig::default().bindings().unwrap(), KeyBindings::default());
+        assert!(Config::parse("[keys]\npreset = \"emacs\"\n").is_err());
+    }
+
+    #[test]
+    fn given_missing_file_when_loaded_or_default_then_default_is_returned() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("no-such-config.toml");
+        assert_eq!(Config::load_or_default(&path).unwrap(), Config::default());
+        assert!(Config::load(&path).is_err());
+    }
+}
@@ -0,0 +1,551 @@
+//! Reinforcement-learning environment.
+//!
+//! `SnakeEnv` wraps `GameLogic` in the interface of gym environments: `reset` starts a new game
+//! with a seed and `step` plays a single tick for an action, returning the observation, the
+//! reward, whether the episode has ended and details of the step. It runs headless and keeps the
+//! observation in a buffer reused by every step, so agents can be trained for millions of steps.
+
+use crate::bot::{Grid, DIRECTIONS};
+use crate::game_logic::{GameLogic, GameSettings, GameStatus};
+use crate::headless::DEFAULT_MAX_TICKS;
+use crate::level::Level;
+use crate::{Direction, Segment, Topology};
+
+/// Number of channels of the grid encoding: head, body, fruit and walls.
+pub const GRID_CHANNELS: usize = 4;
+
+/// Number of features of the egocentric encoding.
+pub const EGOCENTRIC_FEATURES: usize = 12;
+
+/// Actions an agent chooses from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ActionSpace {
+    /// Four actions moving the snake up, right, down and left. Reversing keeps the snake moving
+    /// straight, as in the game.
+    Absolute,
+    /// Three actions keeping the direction, turning left and turning right.
+    Relative,
+}
+
+impl ActionSpace {
+    /// Returns the number of actions.
+    pub fn size(&self) -> usize {
+        match self {
+            ActionSpace::Absolute => DIRECTIONS.len(),
+            ActionSpace::Relative => 3,
+        }
+    }
+
+    // the direction of an action, a snake which has not moved yet heads up
+    fn direction(&self, action: usize, heading: Option<Direction>) -> Direction {
+        assert!(action < self.size(), "Action {} is out of the action space {:?}!", action, self);
+        let heading = heading.unwrap_or(Direction::Up);
+        match (self, action) {
+            (ActionSpace::Absolute, action) => DIRECTIONS[action],
+            (ActionSpace::Relative, 0) => heading,
+            (ActionSpace::Relative, 1) => heading.turn_left(),
+            (ActionSpace::Relative, _) => heading.turn_right(),
+        }
+    }
+}
+
+/// Layout of observations.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ObservationEncoding {
+    /// `GRID_CHANNELS` planes of `height` x `width` fields, row by row: 1 where the head, a body
+    /// segment or a wall lies, and points of fruit divided by the maximal points.
+    Grid,
+    /// `EGOCENTRIC_FEATURES` values seen from the snake's head: danger straight ahead, on the left
+    /// and on the right, the nearest fruit lying ahead, behind, on the left and on the right, the
+    /// direction one-hot in the order up, right, down, left, and the length as a fraction of the
+    /// free fields. A snake which has hit the border sees danger all around and no fruit.
+    Egocentric,
+}
+
+impl ObservationEncoding {
+    /// Returns the dimensions of observations on a `width` x `height` board.
+    pub fn shape(&self, width: usize, height: usize) -> Vec<usize> {
+        match self {
+            ObservationEncoding::Grid => vec![GRID_CHANNELS, height, width],
+            ObservationEncoding::Egocentric => vec![EGOCENTRIC_FEATURES],
+        }
+    }
+}
+
+/// Rewards of the events of a step, summed up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RewardSettings {
+    /// Reward per point of an eaten fruit.
+    pub fruit: f32,
+    /// Reward for losing the game.
+    pub death: f32,
+    /// Reward for winning the game.
+    pub win: f32,
+    /// Reward for every step, usually a small penalty.
+    pub step: f32,
+    /// Reward for moving closer to the nearest fruit, moving away from it is rewarded with the
+    /// negated value.
+    pub approach: f32,
+}
+
+impl Default for RewardSettings {
+    fn default() -> Self {
+        RewardSettings {
+            fruit: 1.0,
+            death: -1.0,
+            win: 10.0,
+            step: 0.0,
+            approach: 0.0,
+        }
+    }
+}
+
+/// Details of a step.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StepInfo {
+    /// Status of the game after the step.
+    pub status: GameStatus,
+    /// Points of the fruit eaten during the step, if any.
+    pub eaten: Option<u8>,
+    /// Score collected so far.
+    pub score: usize,
+    /// Length of the snake, including pending growth.
+    pub length: usize,
+    /// Number of steps played since the reset.
+    pub tick: usize,
+    /// True if the episode was stopped by a ticks limit while the game was still running.
+    pub truncated: bool,
+}
+
+/// Gym-style environment of a snake game.
+pub struct SnakeEnv {
+    level: Level,
+    settings: GameSettings,
+    actions: ActionSpace,
+    encoding: ObservationEncoding,
+    rewards: RewardSettings,
+    max_ticks: usize,
+    max_idle_ticks: Option<usize>,
+    game_logic: GameLogic,
+    idle_ticks: usize,
+    walls: Vec<f32>,
+    observation: Vec<f32>,
+}
+
+impl SnakeEnv {
+    /// Creates an environment with games on an empty `width` x `height` board, the snake starting
+    /// in the middle.
+    pub fn new(width: usize, height: usize) -> SnakeEnv {
+        let start = Segment::new(width as i32 / 2, height as i32 / 2);
+        SnakeEnv::with_level(Level::empty(width, height, start))
+    }
+
+    /// Creates an environment with games starting in the given level.
+    pub fn with_level(level: Level) -> SnakeEnv {
+        let game_logic = GameLogic::from_level(&level, 0);
+        let mut env = SnakeEnv {
+            level,
+            settings: GameSettings::default(),
+            actions: ActionSpace::Absolute,
+            encoding: ObservationEncoding::Grid,
+            rewards: RewardSettings::default(),
+            max_ticks: DEFAULT_MAX_TICKS,
+            max_idle_ticks: None,
+            game_logic,
+            idle_ticks: 0,
+            walls: Vec::new(),
+            observation: Vec::new(),
+        };
+        env.prepare_observation();
+        env
+    }
+
+    /// Sets the shape of the board and starts a new episode with seed 0.
+    pub fn topology(mut self, topology: Topology) -> SnakeEnv {
+        self.level.topology = topology;
+        self.reset(0);
+        self
+    }
+
+    /// Sets the gameplay rules and starts a new episode with seed 0.
+    pub fn settings(mut self, settings: GameSettings) -> SnakeEnv {
+        self.settings = settings;
+        self.reset(0);
+        self
+    }
+
+    /// Sets the actions agents choose from.
+    pub fn actions(mut self, actions: ActionSpace) -> SnakeEnv {
+        self.actions = actions;
+        self
+    }
+
+    /// Sets the layout of observations.
+    pub fn encoding(mut self, encoding: ObservationEncoding) -> SnakeEnv {
+        self.encoding = encoding;
+        self.prepare_observation();
+        self
+    }
+
+    /// Sets the rewards of the events of a step.
+    pub fn rewards(mut self, rewards: RewardSettings) -> SnakeEnv {
+        self.rewards = rewards;
+        self
+    }
+
+    /// Sets the number of ticks after which an episode is truncated.
+    pub fn max_ticks(mut self, max_ticks: usize) -> SnakeEnv {
+        self.max_ticks = max_ticks;
+        self
+    }
+
+    /// Truncates episodes in which the snake has not eaten for the given number of ticks.
+    pub fn max_idle_ticks(mut self, max_idle_ticks: usize) -> SnakeEnv {
+        self.max_idle_ticks = Some(max_idle_ticks);
+        self
+    }
+
+    /// Returns the number of actions.
+    pub fn action_count(&self) -> usize {
+        self.actions.size()
+    }
+
+    /// Returns the dimensions of observations.
+    pub fn observation_shape(&self) -> Vec<usize> {
+        self.encoding.shape(self.level.width, self.level.height)
+    }
+
+    /// Returns the game being played, e.g. to render it.
+    pub fn game_logic(&self) -> &GameLogic {
+        &self.game_logic
+    }
+
+    /// Starts a new episode whose fruit are generated from the seed and returns its first
+    /// observation.
+    pub fn reset(&mut self, seed: u64) -> &[f32] {
+        self.game_logic = GameLogic::from_level(&self.level, seed).with_settings(self.settings.clone());
+        self.idle_ticks = 0;
+        self.prepare_observation();
+        &self.observation
+    }
+
+    // sizes the observation for the level and the encoding, and encodes the current game
+    fn prepare_observation(&mut self) {
+        let (width, height) = (self.level.width, self.level.height);
+        self.walls = vec![0.0; width * height];
+        for &(x, y) in self.level.walls.iter() {
+            self.walls[y * width + x] = 1.0;
+        }
+        self.observation = vec![0.0; self.observation_shape().iter().product()];
+        self.encode();
+    }
+
+    /// Plays a single tick for an action and returns the observation, the reward, whether the
+    /// episode has ended and details of the step. Once the episode has ended steps change
+    /// nothing until the next reset.
+    ///
+    /// Panics if the action is not below `action_count()`.
+    pub fn step(&mut self, action: usize) -> (&[f32], f32, bool, StepInfo) {
+        let direction = self.actions.direction(action, self.game_logic.get_direction());
+        if self.is_done() {
+            let info = self.info(None);
+            return (&self.observation, 0.0, true, info);
+        }
+        let distance_before = self.fruit_distance();
+        let result = self.game_logic.step(Some(direction));
+        self.idle_ticks = if result.eaten.is_some() { 0 } else { self.idle_ticks + 1 };
+
+        let mut reward = self.rewards.step + self.rewards.fruit * result.eaten.unwrap_or(0) as f32;
+        match result.status {
+            GameStatus::Running => {
+                if let (Some(before), Some(after)) = (distance_before, self.fruit_distance()) {
+                    if result.eaten.is_none() && before != after {
+                        reward += if after < before { self.rewards.approach } else { -self.rewards.approach };
+                    }
+                }
+            },
+            GameStatus::GameOver { .. } => reward += self.rewards.death,
+            GameStatus::Won { .. } => reward += self.rewards.win,
+        }
+        self.encode();
+        let info = self.info(result.eaten);
+        (&self.observation, reward, self.is_done(), info)
+    }
+
+    fn is_truncated(&self) -> bool {
+        !self.game_logic.get_status().is_over() && (self.game_logic.get_tick() >= self.max_ticks ||
+            self.max_idle_ticks.is_some_and(|max_idle_ticks| self.idle_ticks >= max_idle_ticks))
+    }
+
+    fn is_done(&self) -> bool {
+        self.game_logic.get_status().is_over() || self.is_truncated()
+    }
+
+    fn info(&self, eaten: Option<u8>) -> StepInfo {
+        StepInfo {
+            status: self.game_logic.get_status(),
+            eaten,
+            score: self.game_logic.get_score(),
+            length: self.game_logic.get_length(),
+            tick: self.game_logic.get_tick(),
+            truncated: self.is_truncated(),
+        }
+    }
+
+    // moves from the head to the nearest fruit on an empty board, `None` without fruit
+    fn fruit_distance(&self) -> Option<usize> {
+        if self.rewards.approach == 0.0 {
+            return None;
+        }
+        let grid = Grid::new(&self.game_logic);
+        grid.fruit().iter().map(|&fruit| grid.distance(grid.head(), fruit)).min()
+    }
+
+    fn encode(&mut self) {
+        match self.encoding {
+            ObservationEncoding::Grid => self.encode_grid(),
+            ObservationEncoding::Egocentric => self.encode_egocentric(),
+        }
+    }
+
+    fn encode_grid(&mut self) {
+        let (width, height) = (self.level.width, self.level.height);
+        let plane = width * height;
+        let max_points = self.settings.max_fruit_points as f32;
+        let observation = &mut self.observation;
+        observation.iter_mut().for_each(|value| *value = 0.0);
+        observation[3 * plane..].copy_from_slice(&self.walls);
+        let snake = self.game_logic.get_snake_segments();
+        // a snake which has hit the border has its head beyond the board
+        let inside = |segment: &Segment| segment.x >= 0 && segment.y >= 0 &&
+            (segment.x as usize) < width && (segment.y as usize) < height;
+        for (index, segment) in snake.iter().enumerate().filter(|(_, segment)| inside(segment)) {
+            let channel = if index == 0 { 0 } else { 1 };
+            observation[channel * plane + segment.y as usize * width + segment.x as usize] = 1.0;
+        }
+        for (x, y, points) in self.game_logic.get_obstacles() {
+            observation[2 * plane + y * width + x] = (points as f32 / max_points).min(1.0);
+        }
+    }
+
+    fn encode_egocentric(&mut self) {
+        let (width, height) = (self.level.width, self.level.height);
+        let head = *self.game_logic.get_snake_segments().front().unwrap();
+        let free_fields = width * height - self.level.walls.len();
+        let mut features = [0.0; EGOCENTRIC_FEATURES];
+        if head.x < 0 || head.y < 0 || head.x as usize >= width || head.y as usize >= height {
+            // a snake which has hit the border sees danger all around and no fruit
+            let heading = self.game_logic.get_direction().unwrap_or(Direction::Up);
+            features[..3].copy_from_slice(&[1.0; 3]);
+            let direction_index = DIRECTIONS.iter().position(|&direction| direction == heading).unwrap();
+            features[7 + direction_index] = 1.0;
+            features[11] = self.game_logic.get_length() as f32 / free_fields as f32;
+            self.observation.copy_from_slice(&features);
+            return;
+        }
+        let grid = Grid::new(&self.game_logic);
+        let heading = grid.direction().unwrap_or(Direction::Up);
+        let danger = |direction: Direction| match grid.neighbour(head, direction) {
+            Some(field) if grid.is_free(field) => 0.0,
+            _ => 1.0,
+        };
+        features[0] = danger(heading);
+        features[1] = danger(heading.turn_left());
+        features[2] = danger(heading.turn_right());
+        if let Some(&fruit) = grid.fruit().iter().min_by_key(|&&fruit| grid.distance(head, fruit)) {
+            let (dx, dy) = self.offset(head, fruit);
+            let (forward, right) = (unit(heading), unit(heading.turn_right()));
+            let ahead = dx * forward.0 + dy * forward.1;
+            let aside = dx * right.0 + dy * right.1;
+            features[3] = if ahead > 0 { 1.0 } else { 0.0 };
+            features[4] = if ahead < 0 { 1.0 } else { 0.0 };
+            features[5] = if aside < 0 { 1.0 } else { 0.0 };
+            features[6] = if aside > 0 { 1.0 } else { 0.0 };
+        }
+        let direction_index = DIRECTIONS.iter().position(|&direction| direction == heading).unwrap();
+        features[7 + direction_index] = 1.0;
+        features[11] = grid.length() as f32 / free_fields as f32;
+        self.observation.copy_from_slice(&features);
+    }
+
+    // shortest offset between two fields, across the edges on a toroidal board
+    fn offset(&self, from: Segment, to: Segment) -> (i32, i32) {
+        let axis = |delta: i32, size: usize| {
+            let size = size as i32;
+            match self.level.topology {
+                Topology::Bounded => delta,
+                Topology::Toroidal if delta > size / 2 => delta - size,
+                Topology::Toroidal if delta < -size / 2 => delta + size,
+                Topology::Toroidal => delta,
+            }
+        };
+        (axis(to.x - from.x, self.level.width), axis(to.y - from.y, self.level.height))
+    }
+}
+
+// steps along x and y of a move in a direction
+fn unit(direction: Direction) -> (i32, i32) {
+    let moved = direction.nearest_segment(&Segment::new(0, 0));
+    (moved.x, moved.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // snake heading right at (1,1) of a 5x3 board, with a 2-point fruit two fields ahead
+    fn get_env() -> SnakeEnv {
+        let level = Level::parse("snake2d-level 1\nsnake 1,1\ndirection right\nmap\n.....\n...2.\n.....\n").unwrap();
+        SnakeEnv::with_level(level).settings(GameSettings::no_spawning())
+    }
+
+    #[test]
+    fn given_grid_encoding_when_reset_then_channels_show_head_fruit_and_walls() {
+        let mut level = Level::empty(4, 3, Segment::new(1, 1));
+        level.walls = vec![(3, 0)];
+        level.fruit = vec![(2, 2, 3)];
+        let mut env = SnakeEnv::with_level(level).settings(GameSettings::no_spawning());
+        assert_eq!(env.observation_shape(), vec![GRID_CHANNELS, 3, 4]);
+        assert_eq!(env.action_count(), 4);
+
+        let observation = env.reset(7).to_vec();
+        assert_eq!(observation.len(), GRID_CHANNELS * 12);
+        let field = |channel: usize, x: usize, y: usize| observation[channel * 12 + y * 4 + x];
+        assert_eq!(field(0, 1, 1), 1.0);
+        assert_eq!(field(2, 2, 2), 1.0);
+        assert_eq!(field(3, 3, 0), 1.0);
+        assert_eq!(observation.iter().sum::<f32>(), 3.0);
+    }
+
+    #[test]
+    fn given_fruit_ahead_when_snake_eats_it_then_reward_counts_points_and_body_is_encoded() {
+        let mut env = get_env().rewards(RewardSettings { step: -0.01, ..RewardSettings::default() });
+        env.reset(0);
+
+        let (_, reward, done, info) = env.step(1);
+        assert_eq!((reward, done, info.eaten), (-0.01, false, None));
+        let (observation, reward, done, info) = env.step(1);
+        assert_eq!((reward, done, info.eaten, info.length, info.tick), (1.99, false, Some(2), 3, 2));
+        assert_eq!(observation[5 + 3], 1.0);
+        let (observation, _, _, _) = env.step(1);
+        assert_eq!((observation[15 + 5 + 3], observation[15 + 5 + 4]), (1.0, 0.0));
+    }
+
+    #[test]
+    fn given_wall_ahead_when_snake_hits_it_then_episode_ends_with_death_reward() {
+        let mut env = get_env();
+        env.reset(0);
+        assert_eq!(env.step(0).1, 0.0);
+
+        let (_, reward, done, info) = env.step(0);
+        assert_eq!((reward, done, info.truncated), (-1.0, true, false));
+        assert!(info.status.is_over());
+        let (_, reward, done, info) = env.step(2);
+        assert_eq!((reward, done, info.tick), (0.0, true, 2));
+        assert!(!env.reset(1).is_empty());
+        assert_eq!(env.game_logic().get_status(), GameStatus::Running);
+    }
+
+    #[test]
+    fn given_target_score_when_game_is_won_then_reward_includes_win_reward() {
+        let level = Level::parse("snake2d-level 1\nsnake 0,0\ndirection right\nmap\n.1.\n").unwrap();
+        let settings = GameSettings { target_score: Some(1), ..GameSettings::no_spawning() };
+        let mut env = SnakeEnv::with_level(level).settings(settings);
+        env.reset(0);
+
+        let (_, reward, done, info) = env.step(1);
+        assert_eq!((reward, done), (11.0, true));
+        assert!(info.status.is_won());
+    }
+
+    #[test]
+    fn given_relative_actions_when_stepping_then_snake_turns_relative_to_heading() {
+        let mut env = get_env().actions(ActionSpace::Relative);
+        env.reset(0);
+        assert_eq!(env.action_count(), 3);
+
+        env.step(2);
+        assert_eq!(env.game_logic().get_direction(), Some(Direction::Down));
+        env.step(1);
+        assert_eq!(env.game_logic().get_direction(), Some(Direction::Right));
+        env.step(0);
+        assert_eq!(env.game_logic().get_direction(), Some(Direction::Right));
+        env.step(1);
+        assert_eq!(env.game_logic().get_direction(), Some(Direction::Up));
+        assert!(!env.game_logic().get_status().is_over());
+    }
+
+    #[test]
+    fn given_builders_when_stepped_without_reset_then_settings_are_in_effect() {
+        let mut env = SnakeEnv::new(8, 8).encoding(ObservationEncoding::Egocentric);
+        let (observation, _, _, _) = env.step(0);
+        assert_eq!(observation.len(), EGOCENTRIC_FEATURES);
+
+        let mut env = SnakeEnv::new(8, 8).topology(Topology::Toroidal).settings(GameSettings::no_spawning());
+        assert_eq!(env.game_logic().get_topology(), Topology::Toroidal);
+        for _ in 0..5 {
+            env.step(0);
+        }
+        assert_eq!(env.game_logic().get_status(), GameStatus::Running);
+        assert_eq!(env.game_logic().get_snake_segments().front().unwrap(), &Segment::new(4, 7));
+    }
+
+    #[test]
+    fn given_egocentric_encoding_then_features_show_danger_fruit_and_direction() {
+        let mut env = get_env().encoding(ObservationEncoding::Egocentric);
+        assert_eq!(env.observation_shape(), vec![EGOCENTRIC_FEATURES]);
+        let observation = env.reset(0).to_vec();
+        // heading right: nothing is in the way, the fruit lies ahead
+        assert_eq!(&observation[..11], &[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+        assert_eq!(observation[11], 1.0 / 15.0);
+
+        // heading up along the top border, the fruit lies behind on the right
+        let (observation, _, _, _) = env.step(0);
+        assert_eq!(&observation[..11], &[1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.0]);
+
+        // beyond the top border there is danger all around and no fruit
+        let (observation, _, done, _) = env.step(0);
+        assert!(done);
+        assert_eq!(&observation[..11], &[1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn given_approach_reward_when_snake_moves_then_moving_away_is_penalized() {
+        let mut env = get_env().rewards(RewardSettings { approach: 0.1, ..RewardSettings::default() });
+        env.reset(0);
+
+        assert_eq!(env.step(1).1, 0.1);
+        assert_eq!(env.step(2).1, -0.1);
+    }
+
+    #[test]
+    fn given_ticks_limits_when_reached_then_episode_is_truncated() {
+        let mut env = SnakeEnv::new(10, 10).topology(Topology::Toroidal).settings(GameSettings::no_spawning())
+            .max_ticks(5);
+        env.reset(0);
+        for _ in 0..4 {
+            assert!(!env.step(1).2);
+        }
+        let (_, reward, done, info) = env.step(1);
+        assert_eq!((reward, done, info.truncated, info.status), (0.0, true, true, GameStatus::Running));
+
+        let mut env = SnakeEnv::new(10, 10).topology(Topology::Toroidal).max_idle_ticks(3);
+        env.reset(0);
+        assert!(!env.step(1).2);
+        assert!(!env.step(1).2);
+        assert!(env.step(1).3.truncated);
+    }
+
+    #[test]
+    fn given_same_seed_when_episodes_are_played_then_observations_are_the_same() {
+        let mut env = SnakeEnv::new(8, 8);
+        let mut play = |seed: u64| {
+            let mut observations = vec![env.reset(seed).to_vec()];
+            for action in [1, 2, 2, 3, 0].iter() {
+                observations.push(env.step(*action).0.to_vec());
+            }
+            observations
+        };
+        assert_eq!(play(3), play(3));
+    }
+}
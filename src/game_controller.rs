@@ -1,7 +1,17 @@
 //! Game controller.
 
+use std::convert::TryFrom;
+
 use piston::input::{GenericEvent, Button, Key};
 use crate::{game_logic, Direction};
+use crate::bot::Player;
+use crate::game_logic::GameSettings;
+use crate::input_queue::InputQueue;
+use crate::key_bindings::{normalize_key_name, Action, KeyBindings};
+use crate::level::Level;
+use crate::renderer::Frame;
+use crate::replay::{Recorder, ReplayPlayer};
+use crate::timestep::FixedTimestep;
 //use crate ex
 
 /// Handles events for snake2d game.
@@ -10,33 +20,418 @@ pub struct GameController {
     pub game_logic: game_logic::GameLogic,
     /// Current direction where snake moves,
     pub direction: Option<Direction>,
+    /// Turns requested by the player which were not applied yet.
+    input_queue: InputQueue,
+    /// Records moves of the game, if recording was started.
+    recorder: Option<Recorder>,
+    /// Provides moves instead of the keyboard when watching a replay.
+    replay_player: Option<ReplayPlayer>,
+    /// Bot providing moves instead of the keyboard.
+    player: Option<Box<dyn Player>>,
+    /// Update events do not advance the game while paused.
+    paused: bool,
+    /// Keys triggering actions.
+    key_bindings: KeyBindings,
+    /// Turns time elapsed in update events into ticks at the tick rate of the game.
+    timestep: FixedTimestep,
+    /// Level the game has started in, restarted games start in it again.
+    start_level: Level,
 }
 
 impl GameController {
     /// Creates a new game logic controller.
     pub fn new(game_logic: game_logic::GameLogic) -> GameController {
         GameController {
+            start_level: game_logic.to_level(),
+            timestep: FixedTimestep::new(game_logic.get_tick_rate()),
             game_logic,
             direction: None,
+            input_queue: InputQueue::default(),
+            recorder: None,
+            replay_player: None,
+            player: None,
+            paused: false,
+            key_bindings: KeyBindings::default(),
         }
     }
 
+    /// Creates a controller which plays back a recorded game, ignoring the keyboard.
+    pub fn with_replay(replay_player: ReplayPlayer) -> GameController {
+        let mut controller = GameController::new(replay_player.replay().new_game());
+        controller.replay_player = Some(replay_player);
+        controller
+    }
+
+    /// Lets a bot play the game, turns requested with the keyboard are ignored.
+    pub fn with_player(mut self, player: Box<dyn Player>) -> GameController {
+        self.player = Some(player);
+        self
+    }
+
+    /// Returns the bot playing the game, if any.
+    pub fn player(&self) -> Option<&dyn Player> {
+        self.player.as_deref()
+    }
+
+    /// Sets the keys triggering actions, arrow keys and the default keys are used otherwise.
+    pub fn with_key_bindings(mut self, key_bindings: KeyBindings) -> GameController {
+        self.key_bindings = key_bindings;
+        self
+    }
+
+    /// Returns the keys triggering actions.
+    pub fn key_bindings(&self) -> &KeyBindings {
+        &self.key_bindings
+    }
+
+    /// Returns the action bound to a key.
+    pub fn key_action(&self, key: Key) -> Option<Action> {
+        key_name(key).and_then(|name| self.key_bindings.action(&name))
+    }
+
+    /// Performs an action triggered by a key. Quitting is left to the caller.
+    pub fn perform(&mut self, action: Action) {
+        match action {
+            Action::Move(direction) => {
+                self.turn(direction);
+            },
+            Action::Pause => self.toggle_pause(),
+            Action::Step if self.paused => self.step(),
+            Action::Restart => self.restart(None),
+            Action::Step | Action::Quit => (),
+        }
+    }
+
+    /// Starts recording moves of the game. Must be called before the first update event.
+    pub fn start_recording(&mut self) {
+        self.recorder = Some(Recorder::new(&self.game_logic));
+    }
+
+    /// Returns the recorder, if recording was started.
+    pub fn recorder(&self) -> Option<&Recorder> {
+        self.recorder.as_ref()
+    }
+
+    /// Starts a new game in the same level and with the same rules as the current one. A seed of
+    /// `None` draws a random one. A replay is watched again from its beginning, ignoring the seed.
+    pub fn restart(&mut self, seed: Option<u64>) {
+        let settings = self.game_logic.get_settings().clone();
+        self.restart_with_settings(settings, seed);
+    }
+
+    /// Starts a new game in the same level with new rules. A seed of `None` draws a random one.
+    pub fn restart_with_settings(&mut self, settings: GameSettings, seed: Option<u64>) {
+        self.game_logic = match &mut self.replay_player {
+            Some(replay_player) => {
+                *replay_player = ReplayPlayer::new(replay_player.replay().clone());
+                replay_player.replay().new_game()
+            },
+            None => game_logic::GameLogic::from_level(&self.start_level, seed.unwrap_or_else(rand::random))
+                .with_settings(settings),
+        };
+        self.timestep = FixedTimestep::new(self.game_logic.get_tick_rate());
+        self.direction = None;
+        self.input_queue.clear();
+        self.paused = false;
+        if self.recorder.is_some() {
+            self.start_recording();
+        }
+    }
+
+    /// Queues a turn requested by the player, one queued turn is applied per tick. Returns false
+    /// if the turn was dropped, also when a bot plays the game.
+    pub fn turn(&mut self, direction: Direction) -> bool {
+        self.player.is_none() && self.input_queue.push(direction, self.game_logic.get_direction())
+    }
+
+    /// Returns true if update events do not advance the game.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Pauses a running game or resumes a paused one.
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Returns a snapshot of the game to be drawn by a renderer, including the paused state and the
+    /// progress of the current tick. The last move of a finished game is shown completed.
+    pub fn frame(&self) -> Frame {
+        let mut frame = self.game_logic.frame();
+        frame.paused = self.paused;
+        frame.progress = if frame.status.is_over() { 1.0 } else { self.timestep.progress() };
+        frame
+    }
+
     /// Handles events.
     pub fn event<E: GenericEvent>(&mut self, e: &E) {
         if let Some(Button::Keyboard(key)) = e.press_args() {
-            if key == Key::Right {
-                self.direction = Some(Direction::Right);
-            } else if key == Key::Left {
-                self.direction = Some(Direction::Left);
-            }  else if key == Key::Up {
-                self.direction = Some(Direction::Up);
-            }  else if key == Key::Down {
-                self.direction = Some(Direction::Down);
+            if let Some(action) = self.key_action(key) {
+                self.perform(action);
             }
         };
 
-        if let Some(_args) = e.update_args() {
-            self.game_logic.main_loop(self.direction);
+        if let Some(args) = e.update_args() {
+            self.advance(args.dt);
+        }
+    }
+
+    /// Advances the game by as many ticks as fit into the elapsed time in seconds, at the tick
+    /// rate of the current speed level. Time does not elapse while the game is paused or over.
+    pub fn advance(&mut self, elapsed: f64) {
+        if self.paused || self.game_logic.get_status().is_over() {
+            return;
         }
+        self.timestep.add_time(elapsed);
+        while self.timestep.take_tick() {
+            self.update();
+            self.timestep.set_tick_rate(self.game_logic.get_tick_rate());
+        }
+    }
+
+    /// Advances the game by one tick, unless the game is paused.
+    pub fn update(&mut self) {
+        if !self.paused {
+            self.step();
+        }
+    }
+
+    /// Advances the game by one tick, also while paused.
+    pub fn step(&mut self) {
+        if self.game_logic.get_status().is_over() {
+            return;
+        }
+        let input = match (&mut self.replay_player, &mut self.player) {
+            (Some(replay_player), _) => match replay_player.next_input() {
+                Some(input) => input,
+                None => return,
+            },
+            (None, Some(player)) => player.next_direction(&self.game_logic),
+            (None, None) => {
+                if let Some(turn) = self.input_queue.pop() {
+                    self.direction = Some(turn);
+                }
+                self.direction
+            },
+        };
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(input);
+        }
+        self.game_logic.main_loop(input);
+    }
+}
+
+// returns the name of a key as used by key bindings
+fn key_name(key: Key) -> Option<String> {
+    match key {
+        Key::Return => Some(String::from("Enter")),
+        _ => match u8::try_from(key.code()) {
+            Ok(code) if code.is_ascii_alphanumeric() => normalize_key_name(&char::from(code).to_string()),
+            _ => normalize_key_name(&format!("{:?}", key)),
+        },
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_logic::{GameLogic, GameStatus};
+    use crate::Segment;
+
+    fn get_controller() -> GameController {
+        let mut controller = GameController::new(GameLogic::with_seed(10, 10, Segment::new(2, 2), 0));
+        controller.direction = Some(Direction::Right);
+        controller
+    }
+
+    #[test]
+    fn given_paused_game_when_updated_then_game_does_not_advance() {
+        let mut controller = get_controller();
+        controller.update();
+        controller.toggle_pause();
+        assert!(controller.is_paused());
+        assert!(controller.frame().paused);
+
+        controller.update();
+        controller.update();
+        assert_eq!(controller.game_logic.get_tick(), 1);
+        assert_eq!(controller.game_logic.get_snake_segments().front(), Some(&Segment::new(3, 2)));
+    }
+
+    #[test]
+    fn given_paused_game_when_stepped_then_game_advances_by_single_ticks() {
+        let mut controller = get_controller();
+        controller.toggle_pause();
+        controller.step();
+        controller.step();
+        assert_eq!(controller.game_logic.get_tick(), 2);
+        assert_eq!(controller.game_logic.get_snake_segments().front(), Some(&Segment::new(4, 2)));
+
+        controller.toggle_pause();
+        assert!(!controller.frame().paused);
+        controller.update();
+        assert_eq!(controller.game_logic.get_tick(), 3);
+    }
+
+    #[test]
+    fn given_finished_game_when_restarted_then_fresh_game_starts_in_same_level() {
+        let level = Level::parse("snake2d-level 1\nsnake 2,1 1,1\ndirection right\nmap\n.....\n.....\n..#..\n").unwrap();
+        let mut controller = GameController::new(GameLogic::from_level(&level, 3));
+        controller.start_recording();
+        controller.direction = Some(Direction::Up);
+        controller.update();
+        controller.update();
+        assert!(controller.game_logic.get_status().is_over());
+
+        controller.toggle_pause();
+        controller.restart(Some(8));
+        assert_eq!(controller.game_logic.get_status(), GameStatus::Running);
+        assert_eq!(controller.game_logic.get_tick(), 0);
+        assert_eq!(controller.game_logic.get_seed(), 8);
+        assert_eq!(controller.game_logic.get_direction(), Some(Direction::Right));
+        assert_eq!(controller.game_logic.to_level(), level);
+        assert_eq!(controller.direction, None);
+        assert!(!controller.is_paused());
+        assert!(controller.recorder().unwrap().replay().inputs.is_empty());
+
+        controller.update();
+        assert_eq!(controller.game_logic.get_snake_segments().front(), Some(&Segment::new(3, 1)));
+    }
+
+    #[test]
+    fn given_new_settings_when_restarted_then_new_game_uses_them() {
+        let mut controller = get_controller();
+        let settings = GameSettings { max_fruit: 1, ..GameSettings::default() };
+        controller.restart_with_settings(settings.clone(), None);
+
+        assert_eq!(controller.game_logic.get_settings(), &settings);
+        controller.restart(None);
+        assert_eq!(controller.game_logic.get_settings(), &settings);
+    }
+
+    #[test]
+    fn given_replay_when_restarted_then_replay_starts_from_beginning() {
+        let mut game_logic = GameLogic::with_seed(10, 10, Segment::new(2, 2), 4);
+        let mut recorder = Recorder::new(&game_logic);
+        for _ in 0..3 {
+            recorder.record(Some(Direction::Down));
+            game_logic.main_loop(Some(Direction::Down));
+        }
+        let mut controller = GameController::with_replay(ReplayPlayer::new(recorder.replay().clone()));
+        for _ in 0..5 {
+            controller.update();
+        }
+        assert_eq!(controller.game_logic.get_tick(), 3);
+
+        controller.restart(None);
+        assert_eq!(controller.game_logic.get_tick(), 0);
+        controller.update();
+        assert_eq!(controller.game_logic.get_snake_segments().front(), Some(&Segment::new(2, 3)));
+    }
+
+    #[test]
+    fn given_two_turns_within_tick_when_updated_then_turns_apply_in_following_ticks() {
+        let mut controller = get_controller();
+        controller.update();
+        assert!(controller.turn(Direction::Up));
+        assert!(controller.turn(Direction::Left));
+
+        controller.update();
+        assert_eq!(controller.game_logic.get_snake_segments().front(), Some(&Segment::new(3, 1)));
+        controller.update();
+        assert_eq!(controller.game_logic.get_snake_segments().front(), Some(&Segment::new(2, 1)));
+        controller.update();
+        assert_eq!(controller.game_logic.get_snake_segments().front(), Some(&Segment::new(1, 1)));
+    }
+
+    #[test]
+    fn given_reversal_when_turned_then_turn_is_dropped() {
+        let mut controller = get_controller();
+        controller.update();
+        assert!(!controller.turn(Direction::Left));
+        assert!(!controller.turn(Direction::Right));
+
+        controller.update();
+        assert_eq!(controller.game_logic.get_snake_segments().front(), Some(&Segment::new(4, 2)));
+    }
+
+    #[test]
+    fn given_wasd_bindings_when_keys_are_mapped_then_actions_are_found() {
+        let controller = get_controller().with_key_bindings(KeyBindings::preset(crate::key_bindings::KeyPreset::Wasd));
+        assert_eq!(controller.key_action(Key::W), Some(Action::Move(Direction::Up)));
+        assert_eq!(controller.key_action(Key::Left), Some(Action::Move(Direction::Left)));
+        assert_eq!(controller.key_action(Key::Escape), Some(Action::Quit));
+        assert_eq!(controller.key_action(Key::F1), None);
+        assert_eq!(key_name(Key::D7), Some(String::from("7")));
+        assert_eq!(key_name(Key::Return), Some(String::from("Enter")));
+    }
+
+    #[test]
+    fn given_actions_when_performed_then_game_is_controlled() {
+        let mut controller = get_controller();
+        controller.perform(Action::Step);
+        assert_eq!(controller.game_logic.get_tick(), 0);
+        controller.perform(Action::Pause);
+        controller.perform(Action::Move(Direction::Down));
+        controller.perform(Action::Step);
+        assert_eq!(controller.game_logic.get_snake_segments().front(), Some(&Segment::new(2, 3)));
+        controller.perform(Action::Restart);
+        assert_eq!(controller.game_logic.get_tick(), 0);
+        assert!(!controller.is_paused());
+    }
+
+    #[test]
+    fn given_elapsed_time_when_advanced_then_game_runs_at_tick_rate() {
+        let mut controller = get_controller();
+        controller.restart_with_settings(GameSettings { tick_rate: 8, ..GameSettings::default() }, Some(0));
+        controller.turn(Direction::Right);
+        controller.advance(0.0625);
+        assert_eq!(controller.game_logic.get_tick(), 0);
+        assert_eq!(controller.frame().progress, 0.5);
+
+        controller.advance(0.125);
+        assert_eq!(controller.game_logic.get_tick(), 1);
+        assert_eq!(controller.frame().interpolated_snake(), vec![(2.5, 2.0)]);
+
+        controller.toggle_pause();
+        controller.advance(0.125);
+        assert_eq!(controller.game_logic.get_tick(), 1);
+    }
+
+    #[test]
+    fn given_game_over_when_advanced_then_last_move_stays_completed() {
+        let mut controller = get_controller();
+        controller.restart_with_settings(GameSettings { tick_rate: 8, ..GameSettings::default() }, Some(0));
+        controller.turn(Direction::Right);
+        for _ in 0..8 {
+            controller.advance(0.125);
+        }
+        assert!(controller.game_logic.get_status().is_over());
+        assert_eq!(controller.game_logic.get_tick(), 8);
+
+        controller.advance(0.0625);
+        assert_eq!(controller.game_logic.get_tick(), 8);
+        assert_eq!(controller.frame().progress, 1.0);
+        assert_eq!(controller.frame().interpolated_snake()[0], (10.0, 2.0));
+    }
+
+    #[test]
+    fn given_bot_when_updated_then_bot_moves_snake_and_keyboard_is_ignored() {
+        struct DownBot;
+        impl Player for DownBot {
+            fn name(&self) -> &str {
+                "down"
+            }
+
+            fn next_direction(&mut self, _game_logic: &GameLogic) -> Option<Direction> {
+                Some(Direction::Down)
+            }
+        }
+        let mut controller = get_controller().with_player(Box::new(DownBot));
+        assert!(!controller.turn(Direction::Left));
+        controller.update();
+
+        assert_eq!(controller.game_logic.get_snake_segments().front(), Some(&Segment::new(2, 3)));
+        assert_eq!(controller.player().map(|player| player.name()), Some("down"));
+    }
+}
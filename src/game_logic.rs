@@ -5,9 +5,18 @@ use crate::board;
 use crate::Direction;
 use crate::Segment;
 use crate::Obstacle;
+use crate::Topology;
+use crate::level::Level;
+use crate::renderer::Frame;
+use crate::score::{Score, ScoreSettings};
+use crate::speed::{SpeedMetric, SpeedSettings};
 
 use std::collections::HashSet;
-use rand::Rng;
+use std::fmt;
+use std::time::Duration;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
 
 /// TODO comment
 #[derive(PartialEq, Debug)]
@@ -18,27 +27,271 @@ enum Collision {
     Snake,
 }
 
+/// Reason why a game has ended.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameOverCause {
+    /// Snake has hit the wall.
+    Wall,
+    /// Snake has hit itself.
+    Snake,
+}
+
+impl fmt::Display for GameOverCause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameOverCause::Wall => write!(f, "You've hit the wall!"),
+            GameOverCause::Snake => write!(f, "You've hit yourself!"),
+        }
+    }
+}
+
+/// Reason why a game has been won.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WinCause {
+    /// Snake fills every field of the board which is not a wall.
+    BoardFilled,
+    /// Snake has reached the target length.
+    TargetLength,
+    /// Score has reached the target score.
+    TargetScore,
+}
+
+impl fmt::Display for WinCause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WinCause::BoardFilled => write!(f, "You've filled the board!"),
+            WinCause::TargetLength => write!(f, "You've reached the target length!"),
+            WinCause::TargetScore => write!(f, "You've reached the target score!"),
+        }
+    }
+}
+
+/// Status of a game, returned by every step of the main loop.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameStatus {
+    /// Game is in progress.
+    Running,
+    /// Game has ended, snake does not move anymore.
+    GameOver {
+        /// What has ended the game.
+        cause: GameOverCause,
+        /// Score collected until the end of the game.
+        score: usize,
+    },
+    /// Game has been won, snake does not move anymore.
+    Won {
+        /// What has won the game.
+        cause: WinCause,
+        /// Score collected until the end of the game.
+        score: usize,
+    },
+}
+
+/// Outcome of a single step of the game.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct StepResult {
+    /// Status of the game after the step.
+    pub status: GameStatus,
+    /// Points of the fruit eaten during the step, if any.
+    pub eaten: Option<u8>,
+    /// Number of steps played since the beginning of the game.
+    pub tick: usize,
+}
+
+impl GameStatus {
+    /// Returns true if the game has ended, either lost or won.
+    pub fn is_over(&self) -> bool {
+        *self != GameStatus::Running
+    }
+
+    /// Returns true if the game has been won.
+    pub fn is_won(&self) -> bool {
+        matches!(self, GameStatus::Won { .. })
+    }
+}
+
+/// Gameplay rules which can be changed in the configuration file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameSettings {
+    /// No fruit are spawned while this many of them lie on the board.
+    pub max_fruit: usize,
+    /// Maximal number of fruit spawned at once.
+    pub max_spawned_fruit: usize,
+    /// Maximal points of a spawned fruit.
+    pub max_fruit_points: u8,
+    /// Spawn fruit on the first tick of the game.
+    pub spawn_on_start: bool,
+    /// Spawn fruit on every tick the snake grows.
+    pub spawn_on_growth: bool,
+    /// Game steps per second at the beginning of the game.
+    pub tick_rate: u64,
+    /// Scoring of eaten fruit.
+    pub score: ScoreSettings,
+    /// Speeding up of the game as the snake grows.
+    pub speed: SpeedSettings,
+    /// Length of the snake, including pending growth, which wins the game. Filling the board
+    /// always wins.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_length: Option<usize>,
+    /// Score which wins the game.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_score: Option<usize>,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        GameSettings {
+            max_fruit: 5,
+            max_spawned_fruit: 2,
+            max_fruit_points: 3,
+            spawn_on_start: true,
+            spawn_on_growth: true,
+            tick_rate: 15,
+            score: ScoreSettings::default(),
+            speed: SpeedSettings::default(),
+            target_length: None,
+            target_score: None,
+        }
+    }
+}
+
+impl GameSettings {
+    /// Checks that fruit can be spawned with these settings.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_spawned_fruit == 0 {
+            return Err(String::from("'max_spawned_fruit' must be positive"));
+        }
+        if self.max_fruit_points == 0 {
+            return Err(String::from("'max_fruit_points' must be positive"));
+        }
+        if self.tick_rate == 0 || self.tick_rate > 1000 {
+            return Err(String::from("'tick_rate' must be between 1 and 1000"));
+        }
+        if self.target_length == Some(0) || self.target_score == Some(0) {
+            return Err(String::from("'target_length' and 'target_score' must be positive"));
+        }
+        self.score.validate()?;
+        self.speed.validate()
+    }
+}
+
+#[cfg(test)]
+impl GameSettings {
+    /// Returns default settings which never spawn fruit, for tests placing fruit themselves.
+    pub(crate) fn no_spawning() -> GameSettings {
+        GameSettings { spawn_on_start: false, spawn_on_growth: false, ..GameSettings::default() }
+    }
+}
+
 pub struct GameLogic {
     board: board::Board,
     snake: snake::Snake,
+    /// Snake segments before the last tick, for renderers drawing between ticks.
+    previous_snake: Vec<Segment>,
     main_loop_counter: usize,
     last_direction: Option<Direction>,
+    status: GameStatus,
+    seed: u64,
+    rng: StdRng,
+    settings: GameSettings,
+    score: Score,
+    play_time: Duration,
 }
 
 impl GameLogic {
+    /// Creates a new game with a random seed.
     pub fn new(width: usize, height: usize, initial_snake_location: Segment) -> GameLogic {
+        GameLogic::with_seed(width, height, initial_snake_location, rand::random())
+    }
+
+    /// Creates a new game whose obstacles are generated from the given seed. The same seed and
+    /// the same sequence of moves passed to `main_loop` always result in the same game.
+    pub fn with_seed(width: usize, height: usize, initial_snake_location: Segment, seed: u64) -> GameLogic {
         let board = board::Board::new(width, height);
-        assert_eq!(board.segment_in(&initial_snake_location), true);
-        let game_logic = GameLogic {
+        assert!(board.segment_in(&initial_snake_location));
+        GameLogic {
             board,
             snake: snake::Snake::new(&vec![initial_snake_location]),
+            previous_snake: Vec::new(),
             main_loop_counter: 0,
             last_direction: None,
-        };
+            status: GameStatus::Running,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            settings: GameSettings::default(),
+            score: Score::default(),
+            play_time: Duration::default(),
+        }
+    }
 
+    /// Creates a new game starting in the state described by the level.
+    pub fn from_level(level: &Level, seed: u64) -> GameLogic {
+        assert_eq!(level.validate(), Ok(()));
+        let mut game_logic = GameLogic::with_seed(level.width, level.height, level.snake[0], seed)
+            .with_topology(level.topology)
+            .with_walls(&level.walls);
+        game_logic.snake = snake::Snake::new(&level.snake);
+        game_logic.last_direction = level.direction;
+        for &(x, y, points) in level.fruit.iter() {
+            game_logic.board.set_obstacle(x, y, points);
+        }
         game_logic
     }
 
+    /// Returns the current state of the game as a level. Fruit placed on the board are included,
+    /// snake growth which is still pending is not.
+    pub fn to_level(&self) -> Level {
+        let (width, height) = self.get_board_size();
+        Level {
+            width,
+            height,
+            topology: self.get_topology(),
+            walls: self.get_walls(),
+            snake: self.snake.body.iter().copied().collect(),
+            direction: self.last_direction,
+            fruit: self.get_obstacles(),
+        }
+    }
+
+    /// Changes the shape of the board. Must be called before the first step of the game.
+    pub fn with_topology(mut self, topology: Topology) -> GameLogic {
+        let (width, height) = self.get_board_size();
+        self.board = board::Board::with_topology(width, height, topology);
+        self
+    }
+
+    /// Places impassable walls on the board. Must be called before the first step of the game.
+    pub fn with_walls(mut self, walls: &[(usize, usize)]) -> GameLogic {
+        for &(x, y) in walls {
+            assert!(!self.snake.body.contains(&Segment::new(x as i32, y as i32)), "Wall placed on the snake!");
+            self.board.set_wall(x, y);
+        }
+        self
+    }
+
+    /// Changes the gameplay rules. Must be called before the first step of the game.
+    pub fn with_settings(mut self, settings: GameSettings) -> GameLogic {
+        assert_eq!(settings.validate(), Ok(()));
+        self.settings = settings;
+        self
+    }
+
+    /// Returns the gameplay rules of this game.
+    pub fn get_settings(&self) -> &GameSettings {
+        &self.settings
+    }
+
+    /// Returns the shape of the board.
+    pub fn get_topology(&self) -> Topology {
+        self.board.topology()
+    }
+
+    /// Returns the seed used to generate obstacles.
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
     pub fn get_board_size(&self) -> (usize, usize) {
         (self.board.width(), self.board.height())
     }
@@ -47,27 +300,121 @@ impl GameLogic {
         self.snake.body.clone()
     }
 
+    fn get_fields(&self) -> Vec<(usize, usize, Obstacle)> {
+        let mut fields = Vec::new();
+        for i in 0..self.board.width() {
+            for j in 0..self.board.height() {
+                let field = self.board.get_field(i, j);
+                if field != Obstacle::None {
+                    fields.push((i, j, field));
+                }
+            }
+        }
+        fields
+    }
+
     pub fn get_obstacles(&self) -> Vec<(usize, usize, u8)> {
         let mut obstacles = Vec::new();
         for i in 0..self.board.width() {
             for j in 0..self.board.height() {
-                match self.board.get_field(i, j) {
-                    Obstacle::Apple(points) => obstacles.push((i, j, points)),
-                    _ => (),
+                if let Obstacle::Apple(points) = self.board.get_field(i, j) {
+                    obstacles.push((i, j, points));
                 }
             }
         }
         obstacles
     }
 
-    pub fn get_points(&self) -> usize {
+    /// Returns positions of all walls on the board.
+    pub fn get_walls(&self) -> Vec<(usize, usize)> {
+        let mut walls = Vec::with_capacity(self.board.get_number_of_walls());
+        for i in 0..self.board.width() {
+            for j in 0..self.board.height() {
+                if self.board.get_field(i, j) == Obstacle::Wall {
+                    walls.push((i, j));
+                }
+            }
+        }
+        walls
+    }
+
+    /// Returns the score collected so far.
+    pub fn get_score(&self) -> usize {
+        self.score.total()
+    }
+
+    /// Returns the current combo multiplier, 1 if no combo is running.
+    pub fn get_combo(&self) -> usize {
+        self.score.combo()
+    }
+
+    /// Returns the speed level reached, starting from 1.
+    pub fn get_speed_level(&self) -> usize {
+        let value = match self.settings.speed.metric {
+            SpeedMetric::Length => self.get_length(),
+            SpeedMetric::Score => self.get_score(),
+        };
+        self.settings.speed.level(value)
+    }
+
+    /// Returns the number of game steps per second at the current speed level.
+    pub fn get_tick_rate(&self) -> u64 {
+        self.settings.speed.tick_rate(self.settings.tick_rate, self.get_speed_level())
+    }
+
+    /// Returns the time the game has been played, at the tick rates of the played steps.
+    pub fn get_play_time(&self) -> Duration {
+        self.play_time
+    }
+
+    /// Returns the length of the snake, including growth which is still pending.
+    pub fn get_length(&self) -> usize {
         self.snake.len()
     }
 
+    /// Returns the length of the snake, which used to be the points of the game.
+    #[deprecated(note = "use `get_length` for the snake length or `get_score` for the score")]
+    pub fn get_points(&self) -> usize {
+        self.get_length()
+    }
+
+    /// Returns the direction the snake has moved in on the last step.
+    pub fn get_direction(&self) -> Option<Direction> {
+        self.last_direction
+    }
+
+    /// Returns current status of the game.
+    pub fn get_status(&self) -> GameStatus {
+        self.status
+    }
+
+    /// Returns the number of steps played since the beginning of the game.
+    pub fn get_tick(&self) -> usize {
+        self.main_loop_counter
+    }
+
+    /// Returns a snapshot of the game to be drawn by a renderer.
+    pub fn frame(&self) -> Frame {
+        Frame {
+            board_size: self.get_board_size(),
+            topology: self.get_topology(),
+            snake: self.snake.body.iter().copied().collect(),
+            previous_snake: self.previous_snake.clone(),
+            progress: 1.0,
+            obstacles: self.get_fields(),
+            score: self.get_score(),
+            length: self.get_length(),
+            combo: self.get_combo(),
+            speed_level: self.get_speed_level(),
+            status: self.status,
+            paused: false,
+            tick: self.main_loop_counter,
+        }
+    }
+
     fn generate_obstacles(&mut self, max_obstacles_count : usize, max_points: u8) {
-        let mut rng = rand::thread_rng();
-        let obstacles_count = rng.gen_range(1, max_obstacles_count + 1);
-        let points = rng.gen::<u8>() % max_points + 1;
+        let obstacles_count = self.rng.gen_range(1, max_obstacles_count + 1);
+        let points = self.rng.gen::<u8>() % max_points + 1;
         let obstacles_positions = self.generate_obstacles_positions(obstacles_count);
         let mut obstacles = HashSet::new();
         for obstacle_position in obstacles_positions {
@@ -82,19 +429,18 @@ impl GameLogic {
         }
     }
 
-    fn generate_obstacles_positions(&self, max_obstacles_count: usize) -> HashSet<(usize, usize)> {
+    fn generate_obstacles_positions(&mut self, max_obstacles_count: usize) -> HashSet<(usize, usize)> {
         assert!(max_obstacles_count > 0);
-        let mut rng = rand::thread_rng();
         let board_capacity = self.board.height() * self.board.width();
-        let obstacles_count = self.board.get_number_of_obstacles();
-        let snake_segments_count = self.snake.body.len() as usize;
+        let obstacles_count = self.board.get_number_of_obstacles() + self.board.get_number_of_walls();
+        let snake_segments_count = self.snake.body.len();
         let mut generated_obstacles_positions = HashSet::new();
         for i in 0..max_obstacles_count {
             if i + 1 + obstacles_count + snake_segments_count > board_capacity {
                 break;
             }
             loop {
-                let random_index = rng.gen::<usize>() % board_capacity;
+                let random_index = self.rng.gen::<usize>() % board_capacity;
                 let segment_guess = self.convert_index_to_coords(random_index);
                 if !generated_obstacles_positions.contains(&(segment_guess.0, segment_guess.1)) &&
                     self.board.get_field(segment_guess.0, segment_guess.1) == Obstacle::None &&
@@ -109,14 +455,14 @@ impl GameLogic {
 
     fn check_collisions(&self) -> Collision {
         for segment in &self.snake.body {
-            if segment.x < 0 || segment.x >= self.board.width() as i32 ||
-                segment.y < 0 || segment.y >= self.board.height() as i32 {
+            if !self.board.segment_in(segment) {
                 return Collision::Wall;
             }
 
-            let obstacle = self.board.get_field(segment.x as usize, segment.y as usize);
-            if obstacle != Obstacle::None {
-                return Collision::Obstacle(obstacle);
+            match self.board.get_field(segment.x as usize, segment.y as usize) {
+                Obstacle::None => (),
+                Obstacle::Wall => return Collision::Wall,
+                obstacle => return Collision::Obstacle(obstacle),
             }
         }
         if !self.snake.has_unique_segments() {
@@ -129,75 +475,126 @@ impl GameLogic {
         ((index / self.board.height()) % self.board.width(), index % self.board.height())
     }
 
-    fn snake_eat(&mut self) {
+    fn snake_eat(&mut self) -> u8 {
         let snake_head = self.snake.body.front().unwrap();
         let points = match self.board.get_field(snake_head.x as usize, snake_head.y as usize) {
             Obstacle::Apple(points) => points,
             _ => panic!("Expected an obstacle to be eaten, got None!"),
         };
         self.board.clear_obstacle(snake_head.x as usize, snake_head.y as usize);
-        self.snake.grow(points as usize);
+        // the snake never grows longer than the fields of the board it can fill
+        self.snake.grow((points as usize).min(self.capacity().saturating_sub(self.snake.len())));
+        points
     }
 
-    pub fn main_loop(&mut self, snake_move: Option<Direction>) {
-        let move_direction = match snake_move {
-            Some(direction) => Some(direction),
-            None => {
-                match self.last_direction {
-                    Some(direction) => Some(direction),
-                    None => None,
-                }
-            }
+    // number of fields the snake can occupy
+    fn capacity(&self) -> usize {
+        self.board.width() * self.board.height() - self.board.get_number_of_walls()
+    }
+
+    fn win_cause(&self) -> Option<WinCause> {
+        if self.snake.body.len() >= self.capacity() {
+            Some(WinCause::BoardFilled)
+        } else if self.settings.target_length.is_some_and(|target| self.get_length() >= target) {
+            Some(WinCause::TargetLength)
+        } else if self.settings.target_score.is_some_and(|target| self.get_score() >= target) {
+            Some(WinCause::TargetScore)
+        } else {
+            None
+        }
+    }
+
+    fn end_game(&mut self, cause: GameOverCause) {
+        self.status = GameStatus::GameOver {
+            cause,
+            score: self.get_score(),
         };
+    }
 
-        if move_direction.is_some() {
-            let mut direction = move_direction.unwrap();
-            if self.last_direction.is_some() {
-                let last_direction = self.last_direction.unwrap();
-                let mut override_direction = false;
-                match direction {
-                    Direction::Up => {
-                        if last_direction == Direction::Down {
-                            override_direction = true;
-                        }
-                    },
-                    Direction::Down => {
-                        if last_direction == Direction::Up {
-                            override_direction = true;
-                        }
-                    },
-                    Direction::Left => {
-                        if last_direction == Direction::Right {
-                            override_direction = true;
-                        }
-                    },
-                    Direction::Right => {
-                        if last_direction == Direction::Left {
-                            override_direction = true;
-                        }
-                    },
-                }
-                if override_direction {
+    fn win_game(&mut self, cause: WinCause) {
+        self.status = GameStatus::Won {
+            cause,
+            score: self.get_score(),
+        };
+    }
+
+    /// Moves the snake by one segment and resolves collisions. Once the game is over the
+    /// snake does not move anymore and the final status is returned.
+    pub fn main_loop(&mut self, snake_move: Option<Direction>) -> GameStatus {
+        self.step(snake_move).status
+    }
+
+    /// Advances the game by a single tick. `input` is the direction requested by the player,
+    /// `None` keeps the snake moving in its last direction. Reversing the snake onto itself is
+    /// ignored. A game which starts already won, e.g. on a single field board, is won by its
+    /// first step without moving the snake.
+    pub fn step(&mut self, input: Option<Direction>) -> StepResult {
+        if self.status.is_over() {
+            return self.step_result(None);
+        }
+        if let Some(cause) = self.win_cause() {
+            self.win_game(cause);
+            return self.step_result(None);
+        }
+        self.main_loop_counter += 1;
+        let tick_rate = self.get_tick_rate();
+        self.play_time += Duration::from_secs(1) / tick_rate as u32;
+        self.previous_snake.clear();
+        self.previous_snake.extend(self.snake.body.iter());
+
+        let mut eaten = None;
+        if let Some(mut direction) = input.or(self.last_direction) {
+            if let Some(last_direction) = self.last_direction {
+                if direction == last_direction.opposite() {
                     direction = last_direction;
                 }
             }
 
-            self.snake.move_body(&direction);
+            self.score.tick(&self.settings.score, self.main_loop_counter);
+            let &snake_head = self.snake.body.front().unwrap();
+            let new_head = self.board.neighbour(&snake_head, &direction);
+            self.last_direction = Some(direction);
+            // on a toroidal board two fields wide or tall the head can wrap onto its own neck
+            if self.snake.body.iter().nth(1) == Some(&new_head) {
+                self.end_game(GameOverCause::Snake);
+                return self.step_result(None);
+            }
+            self.snake.move_body_to(new_head);
             match self.check_collisions() {
-                Collision::Wall => panic!("You've hit the wall!"),
+                Collision::Wall => {
+                    self.end_game(GameOverCause::Wall);
+                    return self.step_result(None);
+                },
                 Collision::Obstacle(_obstacle) => {
-                    self.snake_eat();
+                    let points = self.snake_eat();
+                    self.score.add_fruit(&self.settings.score, points, self.main_loop_counter, tick_rate);
+                    eaten = Some(points);
                 },
                 Collision::None => (),
-                Collision::Snake => panic!("You've hit yourself!"),
+                Collision::Snake => {
+                    self.end_game(GameOverCause::Snake);
+                    return self.step_result(None);
+                },
+            }
+            if let Some(cause) = self.win_cause() {
+                self.win_game(cause);
+                return self.step_result(eaten);
             }
-            self.last_direction = Some(direction);
         }
 
-        self.main_loop_counter += 1;
-        if (self.main_loop_counter == 1 || self.snake.will_grow()) &&
-            self.board.get_number_of_obstacles() < 5 {
-            self.generate_obstacles(2, 3);
+        let spawn = (self.main_loop_counter == 1 && self.settings.spawn_on_start) ||
+            (self.snake.will_grow() && self.settings.spawn_on_growth);
+        if spawn && self.board.get_number_of_obstacles() < self.settings.max_fruit {
+            self.generate_obstacles(self.settings.max_spawned_fruit, self.settings.max_fruit_points);
+        }
+        self.step_result(eaten)
+    }
+
+    fn step_result(&self, eaten: Option<u8>) -> StepResult {
+        StepResult {
+            status: self.status,
+            eaten,
+            tick: self.main_loop_counter,
         }
     }
 }
@@ -258,8 +655,9 @@ mod tests {
         assert_eq!(game_logic.convert_index_to_coords(height*(width-1) + height - 1), (width-1, height -1));
     }
 
+    #[allow(clippy::unnecessary_cast, clippy::single_match, clippy::bool_assert_comparison)]
     fn generate_n_obstacles(n:usize, width:usize, height:usize) {
-        let game_logic = GameLogic::new(width, height, Segment::new(0, 0));
+        let mut game_logic = GameLogic::new(width, height, Segment::new(0, 0));
         let obstacles = game_logic.generate_obstacles_positions(n);
         if n == width * height {
             assert_eq!(obstacles.len(), n - game_logic.snake.body.len() as usize);
@@ -272,7 +670,8 @@ mod tests {
                 Some(erroneous_segment) => panic!("Snake segment ({}, {}) has \
                     the same position as generated obstacle!", erroneous_segment.x, erroneous_segment.y),
                 None => ()
-            }            assert_eq!(game_logic.board.segment_in(&Segment::new(obstacle.0 as i32, obstacle.1 as i32)), true);
+            }
+            assert_eq!(game_logic.board.segment_in(&Segment::new(obstacle.0 as i32, obstacle.1 as i32)), true);
         }
     }
 
@@ -287,13 +686,13 @@ mod tests {
     #[test]
     #[should_panic]
     fn given_mpty_board_when_0_obstacles_are_generated_then_logic_panics() {
-        let game_logic = GameLogic::new(1, 1, Segment::new(0, 0));
+        let mut game_logic = GameLogic::new(1, 1, Segment::new(0, 0));
         let _obstacles = game_logic.generate_obstacles_positions(0);
     }
 
     #[test]
     fn given_empty_board_when_generating_obstacles_above_capacity_then_obstacles_are_generated_to_capacity_only() {
-        let game_logic = GameLogic::new(5, 5, Segment::new(0, 0));
+        let mut game_logic = GameLogic::new(5, 5, Segment::new(0, 0));
         let obstacles = game_logic.generate_obstacles_positions(5*5+1);
         // - 1 since we cannot generate an obstacle where snake segment is
         assert_eq!(obstacles.len(), 5*5 - 1);
@@ -302,21 +701,21 @@ mod tests {
     #[test]
     fn given_empty_big_board_when_generating_many_obstcles_then_execution_should_be_fast() {
         // TODO set timeout for this unit test to 30s
-        let game_logic = GameLogic::new(1000, 1000, Segment::new(0, 0));
+        let mut game_logic = GameLogic::new(1000, 1000, Segment::new(0, 0));
         let obstacles = game_logic.generate_obstacles_positions(1000000);
         assert_eq!(obstacles.len(), 1000000 - game_logic.snake.body.len());
     }
 
     #[test]
     fn given_1x1_board_with_1_segment_snake_when_generating_1_obtacle_then_no_obstacles_are_generated() {
-        let game_logic = GameLogic::new(1, 1, Segment::new(0, 0));
+        let mut game_logic = GameLogic::new(1, 1, Segment::new(0, 0));
         let obstacles = game_logic.generate_obstacles_positions(1);
         assert_eq!(obstacles.len(), 0);
     }
 
     #[test]
     fn given_1x2_board_with_1_segment_snake_when_generating_1_obstacle_then_1_obstacle_is_generated_at_no_snake_position() {
-        let game_logic = GameLogic::new(1, 2, Segment::new(0, 0));
+        let mut game_logic = GameLogic::new(1, 2, Segment::new(0, 0));
         let obstacles = game_logic.generate_obstacles_positions(1);
         assert_eq!(obstacles.len(), 1);
         assert_ne!(obstacles.iter().next().unwrap(), &(0usize, 0usize));
@@ -339,6 +738,7 @@ mod tests {
         assert_eq!(game_logic.snake.body.back().unwrap(), &Segment::new(5, 6));
     }
 
+    #[allow(clippy::needless_late_init, clippy::clone_on_copy, clippy::needless_borrow)]
     fn test_snake_move_expected_collision(game_logic: &mut GameLogic, direction: &Direction) {
         let current_snake_len = game_logic.snake.len();
         let current_snake_front = game_logic.snake.body.front().unwrap();
@@ -499,5 +899,321 @@ mod tests {
         assert_eq!(game_logic.snake.len(), 8);
     }
 
+    #[test]
+    fn given_snake_next_to_wall_when_snake_moves_into_wall_then_game_is_over() {
+        let mut game_logic = GameLogic::new(8, 13, Segment::new(7, 6));
+        assert_eq!(game_logic.get_status(), GameStatus::Running);
+
+        let status = game_logic.main_loop(Some(Direction::Right));
+        assert_eq!(status, GameStatus::GameOver { cause: GameOverCause::Wall, score: 0 });
+        assert_eq!(game_logic.get_status(), status);
+    }
+
+    #[test]
+    fn given_long_snake_when_snake_moves_into_itself_then_game_is_over() {
+        let settings = GameSettings::no_spawning();
+        let mut game_logic = GameLogic::new(8, 13, Segment::new(0, 0)).with_settings(settings);
+        for i in 1..6 {
+            game_logic.board.set_obstacle(i,  0, 1);
+        }
+        for _ in 1..6 {
+            assert_eq!(game_logic.main_loop(Some(Direction::Right)), GameStatus::Running);
+        }
+        assert_eq!(game_logic.main_loop(Some(Direction::Down)), GameStatus::Running);
+        assert_eq!(game_logic.main_loop(Some(Direction::Left)), GameStatus::Running);
+
+        let status = game_logic.main_loop(Some(Direction::Up));
+        assert_eq!(status, GameStatus::GameOver { cause: GameOverCause::Snake, score: 50 });
+        assert_eq!(game_logic.get_length(), 6);
+    }
+
+    #[test]
+    fn given_game_over_when_main_loop_is_called_then_snake_does_not_move() {
+        let mut game_logic = GameLogic::new(8, 13, Segment::new(0, 6));
+        let status = game_logic.main_loop(Some(Direction::Left));
+        assert!(status.is_over());
+        let segments = game_logic.get_snake_segments();
+
+        assert_eq!(game_logic.main_loop(Some(Direction::Down)), status);
+        assert_eq!(game_logic.get_snake_segments(), segments);
+    }
+
+    fn play_seeded_game(seed: u64, moves: &[Direction]) -> GameLogic {
+        let mut game_logic = GameLogic::with_seed(25, 25, Segment::new(12, 12), seed);
+        for &direction in moves {
+            game_logic.main_loop(Some(direction));
+        }
+        game_logic
+    }
+
+    #[test]
+    fn given_same_seed_and_moves_when_games_are_played_then_games_are_identical() {
+        let moves = [Direction::Up, Direction::Up, Direction::Left, Direction::Left, Direction::Down,
+            Direction::Down, Direction::Down, Direction::Right];
+        let first_game = play_seeded_game(42, &moves);
+        let second_game = play_seeded_game(42, &moves);
+
+        assert_eq!(first_game.get_seed(), 42);
+        assert!(!first_game.get_obstacles().is_empty());
+        let mut first_obstacles = first_game.get_obstacles();
+        let mut second_obstacles = second_game.get_obstacles();
+        first_obstacles.sort();
+        second_obstacles.sort();
+        assert_eq!(first_obstacles, second_obstacles);
+        assert_eq!(first_game.get_snake_segments(), second_game.get_snake_segments());
+        assert_eq!(first_game.get_status(), second_game.get_status());
+    }
+
+    #[test]
+    fn given_different_seeds_when_obstacles_are_generated_then_positions_differ() {
+        let mut first_game = GameLogic::with_seed(100, 100, Segment::new(0, 0), 1);
+        let mut second_game = GameLogic::with_seed(100, 100, Segment::new(0, 0), 2);
+
+        assert_ne!(first_game.generate_obstacles_positions(10), second_game.generate_obstacles_positions(10));
+    }
+
+    #[test]
+    fn given_fruit_in_front_of_snake_when_stepping_then_step_result_reports_eaten_fruit() {
+        let mut game_logic = GameLogic::with_seed(8, 13, Segment::new(5, 6), 0);
+        game_logic.board.set_obstacle(6, 6, 3);
+
+        let result = game_logic.step(Some(Direction::Right));
+        assert_eq!(result, StepResult { status: GameStatus::Running, eaten: Some(3), tick: 1 });
+        let result = game_logic.step(None);
+        assert_eq!(result.eaten, None);
+        assert_eq!(result.tick, 2);
+        assert_eq!(game_logic.get_snake_segments().front().unwrap(), &Segment::new(7, 6));
+    }
+
+    #[test]
+    fn given_toroidal_board_when_snake_leaves_board_then_it_enters_on_opposite_edge() {
+        let mut game_logic = GameLogic::with_seed(6, 4, Segment::new(4, 0), 0).with_topology(Topology::Toroidal);
+        assert_eq!(game_logic.get_topology(), Topology::Toroidal);
+
+        game_logic.step(Some(Direction::Right));
+        assert_eq!(game_logic.step(Some(Direction::Right)).status, GameStatus::Running);
+        assert_eq!(game_logic.get_snake_segments().front().unwrap(), &Segment::new(0, 0));
+        assert_eq!(game_logic.step(Some(Direction::Up)).status, GameStatus::Running);
+        assert_eq!(game_logic.get_snake_segments().front().unwrap(), &Segment::new(0, 3));
+        assert_eq!(game_logic.step(Some(Direction::Left)).status, GameStatus::Running);
+        assert_eq!(game_logic.get_snake_segments().front().unwrap(), &Segment::new(5, 3));
+        assert_eq!(game_logic.frame().topology, Topology::Toroidal);
+    }
+
+    #[test]
+    fn given_toroidal_board_when_snake_wraps_into_itself_then_snake_collision_ends_game() {
+        let mut game_logic = GameLogic::with_seed(3, 5, Segment::new(0, 2), 0).with_topology(Topology::Toroidal);
+        game_logic.board.set_obstacle(1, 2, 3);
+        // five obstacles out of the way prevent generating new ones
+        for &(x, y) in [(0, 0), (1, 0), (2, 0), (0, 4), (1, 4)].iter() {
+            game_logic.board.set_obstacle(x, y, 1);
+        }
+        for _ in 0..4 {
+            game_logic.step(Some(Direction::Right));
+        }
+        assert_eq!(game_logic.get_status(),
+                   GameStatus::GameOver { cause: GameOverCause::Snake, score: 30 });
+    }
 
-}
\ No newline at end of file
+    #[test]
+    fn given_narrow_toroidal_board_when_snake_wraps_onto_its_neck_then_snake_collision_ends_game() {
+        let level = Level::parse("snake2d-level 1\nsnake 0,0\ndirection right\ntopology toroidal\nmap\n.1\n..\n..\n")
+            .unwrap();
+        let settings = GameSettings::no_spawning();
+        let mut game_logic = GameLogic::from_level(&level, 0).with_settings(settings);
+        game_logic.step(Some(Direction::Right));
+        game_logic.step(Some(Direction::Right));
+        assert_eq!(game_logic.get_status(), GameStatus::Running);
+
+        let result = game_logic.step(Some(Direction::Right));
+        assert_eq!(result.status, GameStatus::GameOver { cause: GameOverCause::Snake, score: 10 });
+    }
+
+    #[test]
+    fn given_wall_in_front_of_snake_when_snake_moves_into_it_then_game_is_over() {
+        let mut game_logic = GameLogic::with_seed(8, 13, Segment::new(3, 3), 0).with_walls(&[(4, 3), (4, 4)]);
+        assert_eq!(game_logic.get_walls(), vec![(4, 3), (4, 4)]);
+        assert_eq!(game_logic.check_collisions(), Collision::None);
+
+        let status = game_logic.main_loop(Some(Direction::Right));
+        assert_eq!(status, GameStatus::GameOver { cause: GameOverCause::Wall, score: 0 });
+    }
+
+    #[test]
+    fn given_walls_on_board_when_generating_obstacles_then_walls_are_left_free() {
+        let walls: Vec<(usize, usize)> = (0..4).flat_map(|x| (0..4).map(move |y| (x, y)))
+            .filter(|&(x, y)| (x, y) != (0, 0) && (x + y) % 2 == 0)
+            .collect();
+        let mut game_logic = GameLogic::with_seed(4, 4, Segment::new(0, 0), 3).with_walls(&walls);
+        let obstacles = game_logic.generate_obstacles_positions(16);
+
+        // 16 fields - 7 walls - 1 snake segment
+        assert_eq!(obstacles.len(), 8);
+        for obstacle in obstacles {
+            assert!(!walls.contains(&obstacle));
+            assert_ne!(obstacle, (0, 0));
+        }
+    }
+
+    #[test]
+    fn given_walls_on_board_then_frame_contains_walls_and_fruit() {
+        let mut game_logic = GameLogic::with_seed(5, 5, Segment::new(0, 0), 0).with_walls(&[(2, 2)]);
+        game_logic.board.set_obstacle(1, 1, 2);
+        let frame = game_logic.frame();
+        assert_eq!(frame.obstacles, vec![(1, 1, Obstacle::Apple(2)), (2, 2, Obstacle::Wall)]);
+        assert_eq!(game_logic.get_obstacles(), vec![(1, 1, 2)]);
+    }
+
+    #[test]
+    fn given_fruit_settings_when_game_starts_then_fruit_are_spawned_accordingly() {
+        let settings = GameSettings { max_spawned_fruit: 1, max_fruit_points: 1, ..GameSettings::default() };
+        for seed in 0..20 {
+            let mut game_logic = GameLogic::with_seed(10, 10, Segment::new(5, 5), seed).with_settings(settings.clone());
+            game_logic.step(None);
+            assert_eq!(game_logic.get_obstacles().len(), 1);
+            assert!(game_logic.get_obstacles().iter().all(|&(_, _, points)| points == 1));
+        }
+    }
+
+    #[test]
+    fn given_spawn_triggers_disabled_when_game_is_played_then_no_fruit_are_spawned() {
+        let settings = GameSettings::no_spawning();
+        let mut game_logic = GameLogic::with_seed(10, 10, Segment::new(5, 5), 0).with_settings(settings);
+        game_logic.board.set_obstacle(6, 5, 1);
+        game_logic.step(Some(Direction::Right));
+        game_logic.step(Some(Direction::Right));
+
+        assert_eq!(game_logic.get_length(), 2);
+        assert!(game_logic.get_obstacles().is_empty());
+    }
+
+    #[test]
+    fn given_fruit_cap_when_fruit_lie_on_board_then_no_fruit_are_spawned() {
+        let settings = GameSettings { max_fruit: 1, ..GameSettings::default() };
+        let mut game_logic = GameLogic::with_seed(10, 10, Segment::new(5, 5), 0).with_settings(settings);
+        game_logic.board.set_obstacle(0, 0, 1);
+        game_logic.step(None);
+
+        assert_eq!(game_logic.get_obstacles(), vec![(0, 0, 1)]);
+    }
+
+    #[test]
+    fn given_invalid_settings_then_validation_fails() {
+        assert_eq!(GameSettings::default().validate(), Ok(()));
+        assert!(GameSettings { max_spawned_fruit: 0, ..GameSettings::default() }.validate().is_err());
+        assert!(GameSettings { max_fruit_points: 0, ..GameSettings::default() }.validate().is_err());
+        assert!(GameSettings { target_length: Some(0), ..GameSettings::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn given_fruit_when_eaten_then_score_is_counted_separately_from_length() {
+        let mut settings = GameSettings::no_spawning();
+        settings.score.fruit_scores = vec![5, 100];
+        settings.score.combo_ticks = 2;
+        let mut game_logic = GameLogic::with_seed(10, 3, Segment::new(0, 1), 0).with_settings(settings);
+        game_logic.board.set_obstacle(1, 1, 2);
+        game_logic.board.set_obstacle(2, 1, 1);
+        game_logic.board.set_obstacle(6, 1, 1);
+
+        game_logic.step(Some(Direction::Right));
+        assert_eq!((game_logic.get_score(), game_logic.get_length(), game_logic.get_combo()), (100, 3, 1));
+        game_logic.step(None);
+        assert_eq!((game_logic.get_score(), game_logic.get_length(), game_logic.get_combo()), (110, 4, 2));
+        for _ in 0..3 {
+            game_logic.step(None);
+        }
+        assert_eq!(game_logic.get_combo(), 1);
+        game_logic.step(None);
+        assert_eq!((game_logic.get_score(), game_logic.get_length()), (115, 5));
+        assert_eq!(game_logic.frame().score, 115);
+        assert_eq!(game_logic.frame().length, 5);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn given_deprecated_points_then_snake_length_is_returned() {
+        let mut settings = GameSettings::no_spawning();
+        settings.score.fruit_scores = vec![5];
+        let mut game_logic = GameLogic::with_seed(10, 3, Segment::new(0, 1), 0).with_settings(settings);
+        game_logic.board.set_obstacle(1, 1, 1);
+
+        game_logic.step(Some(Direction::Right));
+        assert_eq!(game_logic.get_points(), game_logic.get_length());
+        assert_ne!(game_logic.get_points(), game_logic.get_score());
+    }
+
+    #[test]
+    fn given_speed_thresholds_when_snake_grows_then_tick_rate_increases_up_to_cap() {
+        let settings = GameSettings {
+            tick_rate: 10,
+            speed: SpeedSettings { thresholds: vec![2, 4], step: 5, max_tick_rate: 18, ..SpeedSettings::default() },
+            ..GameSettings::no_spawning()
+        };
+        let mut game_logic = GameLogic::with_seed(10, 1, Segment::new(0, 0), 0).with_settings(settings);
+        game_logic.set_obstacles(&[(1, 0, 1), (2, 0, 3)].iter().copied().collect());
+        assert_eq!((game_logic.get_speed_level(), game_logic.get_tick_rate()), (1, 10));
+
+        game_logic.step(Some(Direction::Right));
+        assert_eq!((game_logic.get_speed_level(), game_logic.get_tick_rate()), (2, 15));
+        game_logic.step(Some(Direction::Right));
+        assert_eq!((game_logic.get_speed_level(), game_logic.get_tick_rate()), (3, 18));
+        assert_eq!(game_logic.frame().speed_level, 3);
+        assert_eq!(game_logic.get_play_time(), Duration::from_nanos(100_000_000 + 66_666_666));
+    }
+
+    #[test]
+    fn given_one_free_field_left_when_snake_fills_it_then_game_is_won() {
+        let settings = GameSettings::no_spawning();
+        let mut game_logic = GameLogic::with_seed(3, 2, Segment::new(0, 0), 0)
+            .with_walls(&[(2, 0), (2, 1)])
+            .with_settings(settings);
+        game_logic.set_obstacles(&[(1, 0, 3)].iter().copied().collect());
+
+        assert_eq!(game_logic.step(Some(Direction::Right)).status, GameStatus::Running);
+        // growth is capped at the free fields of the board
+        assert_eq!(game_logic.get_length(), 4);
+        assert_eq!(game_logic.step(Some(Direction::Down)).status, GameStatus::Running);
+        assert_eq!(game_logic.step(Some(Direction::Left)).status, GameStatus::Running);
+        let status = game_logic.step(Some(Direction::Up)).status;
+        assert_eq!(status, GameStatus::Won { cause: WinCause::BoardFilled, score: game_logic.get_score() });
+        assert!(status.is_over() && status.is_won());
+        assert_eq!(game_logic.step(Some(Direction::Right)).tick, 4);
+        assert_eq!(game_logic.frame().status, status);
+    }
+
+    #[test]
+    fn given_targets_when_snake_reaches_them_then_game_is_won() {
+        let settings = GameSettings::no_spawning();
+        let new_game = |settings: GameSettings| {
+            let mut game_logic = GameLogic::with_seed(10, 1, Segment::new(0, 0), 0).with_settings(settings);
+            game_logic.set_obstacles(&[(1, 0, 3)].iter().copied().collect());
+            game_logic
+        };
+
+        let mut game_logic = new_game(GameSettings { target_length: Some(4), ..settings.clone() });
+        assert!(matches!(game_logic.step(Some(Direction::Right)).status,
+                         GameStatus::Won { cause: WinCause::TargetLength, .. }));
+
+        let mut game_logic = new_game(GameSettings { target_length: Some(5), ..settings.clone() });
+        assert_eq!(game_logic.step(Some(Direction::Right)).status, GameStatus::Running);
+
+        let mut game_logic = new_game(GameSettings { target_score: Some(1), ..settings });
+        let result = game_logic.step(Some(Direction::Right));
+        assert_eq!(result.eaten, Some(3));
+        assert!(matches!(result.status, GameStatus::Won { cause: WinCause::TargetScore, .. }));
+    }
+
+    #[test]
+    fn given_game_won_from_start_when_stepped_then_game_is_won() {
+        let mut game_logic = GameLogic::with_seed(1, 1, Segment::new(0, 0), 0);
+        let result = game_logic.step(None);
+        assert_eq!(result.status, GameStatus::Won { cause: WinCause::BoardFilled, score: 0 });
+        assert_eq!(result.tick, 0);
+
+        let settings = GameSettings { target_length: Some(1), ..GameSettings::default() };
+        let mut game_logic = GameLogic::with_seed(10, 10, Segment::new(0, 0), 0).with_settings(settings);
+        assert!(matches!(game_logic.step(Some(Direction::Right)).status,
+                         GameStatus::Won { cause: WinCause::TargetLength, .. }));
+        assert_eq!(game_logic.get_snake_segments().front().unwrap(), &Segment::new(0, 0));
+    }
+}
@@ -1,10 +1,13 @@
 //! Game view.
 
-use graphics::types::Color;
+use graphics::character::CharacterCache;
+use graphics::types::{Color, FontSize};
 use graphics::{Context, Graphics, Transformed, ImageSize};
-use opengl_graphics::{Filter, Texture, TextureSettings};
+use opengl_graphics::{Filter, GlyphCache, Texture, TextureSettings};
 
 use crate::game_controller;
+use crate::renderer::{Frame, Renderer};
+use crate::Obstacle;
 use graphics::math::Scalar;
 use std::collections::{HashMap};
 use glob::glob;
@@ -12,19 +15,26 @@ use glob::glob;
 /// Stores game view settings.
 pub struct GameViewSettings {
     /// Background color
-    background_color: Color,
-    /// Border color
-    border_color: Color,
+    pub(crate) background_color: Color,
     /// Snake body color
-    snake_body_color: Color,
+    pub(crate) snake_body_color: Color,
     /// Snake head color
-    snake_head_color: Color,
+    pub(crate) snake_head_color: Color,
+    /// Wall color
+    pub(crate) wall_color: Color,
+    /// Color of fruit without a texture
+    pub(crate) fruit_color: Color,
+    /// Color of the overlay covering the board once the game is over
+    pub(crate) game_over_color: Color,
+    /// Color of the overlay covering the board once the game is won
+    pub(crate) game_won_color: Color,
     /// scoring of obstacles
-    scores_lookup: HashMap<u8, String>,
+    pub(crate) scores_lookup: HashMap<u8, String>,
 }
 
 impl GameViewSettings {
     /// Creates new game view settings.
+    #[allow(clippy::new_without_default)]
     pub fn new() -> GameViewSettings {
         let mut scores: HashMap<u8, String> = HashMap::new();
         scores.insert(1, String::from("apple"));
@@ -32,20 +42,35 @@ impl GameViewSettings {
         scores.insert(3, String::from("cherry"));
         GameViewSettings {
             background_color: [1.0, 1.0, 1.0, 1.0],
-            border_color: [1.0, 1.0, 1.0, 1.0],
             snake_body_color: [0.0, 0.0, 0.7, 1.0],
             snake_head_color: [0.9, 0.0, 0.0, 1.0],
+            wall_color: [0.3, 0.3, 0.3, 1.0],
+            fruit_color: [0.0, 0.6, 0.0, 1.0],
+            game_over_color: [0.0, 0.0, 0.0, 0.5],
+            game_won_color: [0.0, 0.6, 0.0, 0.4],
             scores_lookup: scores,
         }
     }
+
+    /// Returns the name of the fruit worth given points.
+    pub fn fruit_name(&self, points: u8) -> Option<&str> {
+        self.scores_lookup.get(&points).map(String::as_str)
+    }
 }
 
+/// Color of text drawn over the board
+const TEXT_COLOR: Color = [1.0, 1.0, 1.0, 1.0];
+/// Size of text which fits the screen
+const MAX_FONT_SIZE: FontSize = 16;
+
 /// Stores visual information about a game.
 pub struct GameView {
     /// Stores game view settings.
     pub settings: GameViewSettings,
     /// lookup for textures
     pub textures: HashMap<String, Texture>,
+    /// font of text drawn over the board
+    glyphs: Option<GlyphCache<'static>>,
 }
 
 impl GameView {
@@ -54,10 +79,12 @@ impl GameView {
         GameView {
             settings,
             textures: Default::default(),
+            glyphs: None,
         }
     }
 
-    /// loads textures from asset folder
+    /// loads textures and the font from asset folder
+    #[allow(clippy::manual_flatten)]
     pub fn load_textures(&mut self, asset_folder : &str) {
         let assets = find_folder::Search::ParentsThenKids(3, 3).for_folder(asset_folder).unwrap();
         for entry in glob(assets.join("*.png").to_str().unwrap()).expect("Failed to find textures in asset directory!") {
@@ -72,6 +99,12 @@ impl GameView {
         if self.textures.is_empty() {
             println!("WARNING: No textures loaded!");
         }
+        let font = glob(assets.join("*.ttf").to_str().unwrap()).expect("Failed to find fonts in asset directory!")
+            .flatten().next();
+        self.glyphs = font.and_then(|path| GlyphCache::new(path, (), TextureSettings::new()).ok());
+        if self.glyphs.is_none() {
+            println!("WARNING: No font loaded!");
+        }
     }
 
     fn load_texture_from_path(&self, path: &str) -> Texture {
@@ -82,6 +115,7 @@ impl GameView {
     // TODO it's not possible to unit-test below method as in order to create a Texture object
     // (even empty one) requires a gl context to be created
     // see https://github.com/PistonDevelopers/opengl_graphics/issues/103
+    // the drawn scene is covered by golden image tests of `software_view::SoftwareView` instead
     fn get_scaling_factor(&self, texture: &Texture, desired_size: Scalar) -> Scalar {
         assert!(desired_size > 0.0);
         assert_eq!(texture.get_height(), texture.get_width());
@@ -99,31 +133,107 @@ impl GameView {
         graphics::image(texture, t, g);
     }
 
+    /// Draws lines of text in the middle of a screen, shrinking the text until the lines fit.
+    pub fn draw_text_lines<G: Graphics<Texture = Texture>>(&mut self, lines: &[String], c: &Context, g: &mut G,
+                                                           screen_size: [f64;2]) {
+        let glyphs = match self.glyphs.as_mut() {
+            Some(glyphs) => glyphs,
+            None => return,
+        };
+        let widest = lines.iter()
+            .filter_map(|line| glyphs.width(MAX_FONT_SIZE, line).ok())
+            .fold(0.0, f64::max);
+        let scale = if widest > 0.0 { (screen_size[0] * 0.95 / widest).min(1.0) } else { 1.0 };
+        let font_size = ((MAX_FONT_SIZE as f64 * scale).floor() as FontSize).max(1);
+        let line_height = font_size as f64 * 1.2;
+        let left = ((screen_size[0] - widest * scale) / 2.0).max(0.0);
+        let top = ((screen_size[1] - line_height * lines.len() as f64) / 2.0).max(0.0);
+        for (row, line) in lines.iter().enumerate() {
+            let transform = c.transform.trans(left, top + line_height * (row + 1) as f64);
+            if graphics::text(TEXT_COLOR, font_size, line, glyphs, transform, g).is_err() {
+                return;
+            }
+        }
+    }
+
     /// Draws all objects on a screen
     pub fn draw<G: Graphics<Texture = Texture>>(&self, controller: &game_controller::GameController, c: &Context, g: &mut G, screen_size: [f64;2]) {
-        let board_size = controller.game_logic.get_board_size();
+        self.draw_frame(&controller.game_logic.frame(), c, g, screen_size);
+    }
+
+    /// Draws a snapshot of a game on a screen
+    #[allow(clippy::needless_borrow)]
+    pub fn draw_frame<G: Graphics<Texture = Texture>>(&self, frame: &Frame, c: &Context, g: &mut G, screen_size: [f64;2]) {
+        let board_size = frame.board_size;
         let (segment_size, segment_height) = (screen_size[0] / board_size.0 as f64, screen_size[1] / board_size.1 as f64);
         graphics::clear(self.settings.background_color, g);
-        let &snake_head_segment = controller.game_logic.get_snake_segments().front().unwrap();
 
-        for &segment in controller.game_logic.get_snake_segments().iter() {
-            let color = if segment == snake_head_segment {
+        for (index, &(x, y)) in frame.interpolated_snake().iter().enumerate().rev() {
+            let color = if index == 0 {
                 self.settings.snake_head_color
             } else {
                 self.settings.snake_body_color
             };
-            let square = graphics::rectangle::square(segment.x as f64 * segment_size,
-                                           segment.y as f64 * segment_height, segment_size);
+            let square = graphics::rectangle::square(x * segment_size, y * segment_height, segment_size);
             graphics::rectangle(color, square, c.transform, g);
         }
 
-        for obstacle in controller.game_logic.get_obstacles() {
-            let obstacle_left_upper_corner_position = [obstacle.0 as f64 * segment_size,
-                obstacle.1 as f64 * segment_height];
-            let points = obstacle.2;
-            let texture_name = self.settings.scores_lookup.get(&points).unwrap();
-            let texture = self.textures.get(texture_name).unwrap();
-            self.draw_texture_at_position( &c, g, texture, obstacle_left_upper_corner_position, segment_size);
+        for &(x, y, obstacle) in frame.obstacles.iter() {
+            let obstacle_left_upper_corner_position = [x as f64 * segment_size, y as f64 * segment_height];
+            match obstacle {
+                Obstacle::Apple(points) => {
+                    let texture = self.settings.scores_lookup.get(&points)
+                        .and_then(|texture_name| self.textures.get(texture_name));
+                    match texture {
+                        Some(texture) => self.draw_texture_at_position( &c, g, texture,
+                            obstacle_left_upper_corner_position, segment_size),
+                        None => {
+                            let rectangle = [obstacle_left_upper_corner_position[0],
+                                obstacle_left_upper_corner_position[1], segment_size, segment_height];
+                            graphics::rectangle(self.settings.fruit_color, rectangle, c.transform, g);
+                        },
+                    }
+                },
+                Obstacle::Wall => {
+                    let rectangle = [obstacle_left_upper_corner_position[0], obstacle_left_upper_corner_position[1],
+                        segment_size, segment_height];
+                    graphics::rectangle(self.settings.wall_color, rectangle, c.transform, g);
+                },
+                Obstacle::None => (),
+            }
+        }
+
+        if frame.status.is_over() {
+            let color = if frame.status.is_won() { self.settings.game_won_color } else { self.settings.game_over_color };
+            graphics::rectangle(color, [0.0, 0.0, screen_size[0], screen_size[1]], c.transform, g);
+        }
+    }
+}
+
+/// Renders frames with OpenGL, using textures and settings of a game view.
+pub struct GlRenderer<'a, G: Graphics<Texture = Texture>> {
+    view: &'a GameView,
+    context: Context,
+    graphics: &'a mut G,
+    screen_size: [f64; 2],
+}
+
+impl<'a, G: Graphics<Texture = Texture>> GlRenderer<'a, G> {
+    /// Creates a renderer drawing with the given graphics backend.
+    pub fn new(view: &'a GameView, context: Context, graphics: &'a mut G, screen_size: [f64; 2]) -> Self {
+        GlRenderer {
+            view,
+            context,
+            graphics,
+            screen_size,
         }
     }
-}
\ No newline at end of file
+}
+
+impl<'a, G: Graphics<Texture = Texture>> Renderer for GlRenderer<'a, G> {
+    type Output = ();
+
+    fn render(&mut self, frame: &Frame) {
+        self.view.draw_frame(frame, &self.context, self.graphics, self.screen_size);
+    }
+}
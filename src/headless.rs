@@ -0,0 +1,183 @@
+//! Running games without a window.
+//!
+//! `HeadlessRunner` plays complete games by calling `GameLogic::step` in a tight loop, asking an
+//! input function for the move on every tick. It needs neither a window nor a GL context, so it
+//! can be used by bots, fuzzers and for batch statistics.
+
+use crate::game_logic::{GameLogic, GameSettings, GameStatus};
+use crate::level::Level;
+use crate::{Direction, Segment, Topology};
+
+/// Default limit of ticks after which a game which has not ended is stopped.
+pub const DEFAULT_MAX_TICKS: usize = 100_000;
+
+/// Result of a single headless game.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GameSummary {
+    /// Seed the game was played with.
+    pub seed: u64,
+    /// Status of the game when it was stopped. `GameStatus::Running` means the game reached the
+    /// ticks limit.
+    pub status: GameStatus,
+    /// Score collected during the game.
+    pub score: usize,
+    /// Length of the snake at the end of the game.
+    pub length: usize,
+    /// Number of ticks played.
+    pub ticks: usize,
+}
+
+/// Plays games starting in the same level.
+pub struct HeadlessRunner {
+    level: Level,
+    settings: GameSettings,
+    max_ticks: usize,
+}
+
+impl HeadlessRunner {
+    /// Creates a runner for games on an empty `width` x `height` board.
+    pub fn new(width: usize, height: usize, starting_segment: Segment) -> HeadlessRunner {
+        HeadlessRunner::with_level(Level::empty(width, height, starting_segment))
+    }
+
+    /// Creates a runner for games starting in the given level.
+    pub fn with_level(level: Level) -> HeadlessRunner {
+        HeadlessRunner {
+            level,
+            settings: GameSettings::default(),
+            max_ticks: DEFAULT_MAX_TICKS,
+        }
+    }
+
+    /// Sets the number of ticks after which a game which has not ended is stopped.
+    pub fn max_ticks(mut self, max_ticks: usize) -> HeadlessRunner {
+        self.max_ticks = max_ticks;
+        self
+    }
+
+    /// Sets the shape of the board.
+    pub fn topology(mut self, topology: Topology) -> HeadlessRunner {
+        self.level.topology = topology;
+        self
+    }
+
+    /// Sets the gameplay rules.
+    pub fn settings(mut self, settings: GameSettings) -> HeadlessRunner {
+        self.settings = settings;
+        self
+    }
+
+    /// Creates a new game played with the given seed.
+    pub fn new_game(&self, seed: u64) -> GameLogic {
+        GameLogic::from_level(&self.level, seed).with_settings(self.settings.clone())
+    }
+
+    /// Plays a single game with the given seed. `input` is asked for the move before every tick.
+    pub fn run<F>(&self, seed: u64, input: F) -> GameSummary
+        where F: FnMut(&GameLogic) -> Option<Direction> {
+        let mut game_logic = self.new_game(seed);
+        self.run_game(&mut game_logic, input)
+    }
+
+    /// Plays an already created game until it ends or reaches the ticks limit.
+    pub fn run_game<F>(&self, game_logic: &mut GameLogic, mut input: F) -> GameSummary
+        where F: FnMut(&GameLogic) -> Option<Direction> {
+        while game_logic.get_tick() < self.max_ticks {
+            let direction = input(game_logic);
+            if game_logic.step(direction).status.is_over() {
+                break;
+            }
+        }
+        GameSummary {
+            seed: game_logic.get_seed(),
+            status: game_logic.get_status(),
+            score: game_logic.get_score(),
+            length: game_logic.get_length(),
+            ticks: game_logic.get_tick(),
+        }
+    }
+
+    /// Plays one game per seed. `make_input` creates a fresh input function for every game.
+    pub fn run_many<I, M, F>(&self, seeds: I, mut make_input: M) -> Vec<GameSummary>
+        where I: IntoIterator<Item = u64>,
+              M: FnMut() -> F,
+              F: FnMut(&GameLogic) -> Option<Direction> {
+        seeds.into_iter().map(|seed| self.run(seed, make_input())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_logic::GameOverCause;
+
+    #[test]
+    fn given_snake_moving_right_when_game_is_run_then_snake_hits_the_wall() {
+        let runner = HeadlessRunner::new(10, 10, Segment::new(2, 5));
+        let summary = runner.run(3, |_| Some(Direction::Right));
+
+        assert_eq!(summary.ticks, 8);
+        match summary.status {
+            GameStatus::GameOver { cause: GameOverCause::Wall, score } => assert_eq!(score, summary.score),
+            status => panic!("Expected game over by wall, got {:?}", status),
+        }
+    }
+
+    #[test]
+    fn given_toroidal_board_when_snake_moves_right_then_game_does_not_end() {
+        let runner = HeadlessRunner::new(10, 10, Segment::new(2, 5)).topology(Topology::Toroidal).max_ticks(100);
+        let summary = runner.run(3, |_| Some(Direction::Right));
+
+        assert_eq!(summary.ticks, 100);
+        assert_eq!(summary.status, GameStatus::Running);
+    }
+
+    #[test]
+    fn given_level_when_game_is_run_then_game_starts_in_level() {
+        let mut level = Level::empty(10, 3, Segment::new(2, 1));
+        level.walls = vec![(6, 1)];
+        let runner = HeadlessRunner::with_level(level);
+        let summary = runner.run(3, |_| Some(Direction::Right));
+
+        assert_eq!(summary.ticks, 4);
+        assert!(summary.status.is_over());
+    }
+
+    #[test]
+    fn given_snake_not_moving_when_game_is_run_then_game_stops_at_ticks_limit() {
+        let runner = HeadlessRunner::new(10, 10, Segment::new(2, 5)).max_ticks(50);
+        let summary = runner.run(3, |_| None);
+
+        assert_eq!(summary.status, GameStatus::Running);
+        assert_eq!(summary.ticks, 50);
+        assert_eq!(summary.score, 0);
+        assert_eq!(summary.length, 1);
+    }
+
+    // circles clockwise around the board, turning at the borders
+    fn circle_around(game_logic: &GameLogic) -> Option<Direction> {
+        let head = *game_logic.get_snake_segments().front().unwrap();
+        let (width, height) = game_logic.get_board_size();
+        let (max_x, max_y) = (width as i32 - 1, height as i32 - 1);
+        if head.y == 0 && head.x < max_x {
+            Some(Direction::Right)
+        } else if head.x == max_x && head.y < max_y {
+            Some(Direction::Down)
+        } else if head.y == max_y && head.x > 0 {
+            Some(Direction::Left)
+        } else {
+            Some(Direction::Up)
+        }
+    }
+
+    #[test]
+    fn given_many_seeds_when_games_are_run_then_results_are_reproducible() {
+        let runner = HeadlessRunner::new(12, 12, Segment::new(0, 0)).max_ticks(2_000);
+        let first_run = runner.run_many(0..200, || circle_around);
+        let second_run = runner.run_many(0..200, || circle_around);
+
+        assert_eq!(first_run.len(), 200);
+        assert_eq!(first_run, second_run);
+        assert!(first_run.iter().any(|summary| summary.score > 0 && summary.length > 1));
+    }
+}
@@ -0,0 +1,399 @@
+//! Table of the best games, persisted between runs.
+//!
+//! The table is a TOML file, by default `snake2d/scores.toml` in the user's data directory (e.g.
+//! `~/.local/share` on Linux), holding one `[[entries]]` table per game, best first:
+//!
+//! ```toml
+//! [[entries]]
+//! player = "marcin"
+//! score = 120
+//! length = 14
+//! board_size = [25, 25]
+//! mode = "classic"
+//! duration_secs = 52
+//! date = 1700000000
+//! ```
+//!
+//! `date` is the number of seconds since the Unix epoch when the game has ended.
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game_logic::GameLogic;
+
+/// Name of the high-score file in the user's data directory.
+pub const HIGH_SCORES_FILE_NAME: &str = "scores.toml";
+
+/// Default number of games kept in the table.
+pub const DEFAULT_CAPACITY: usize = 10;
+
+/// Error returned when the high-score table cannot be read or written.
+#[derive(Debug)]
+pub enum HighScoresError {
+    /// Reading or writing the high-score file has failed.
+    Io(io::Error),
+    /// High-score file is malformed.
+    Format {
+        /// Line (counted from 1) where the problem was found, 0 if the problem concerns the
+        /// file as a whole.
+        line: usize,
+        /// Description of the problem.
+        message: String,
+    },
+}
+
+impl fmt::Display for HighScoresError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HighScoresError::Io(error) => write!(f, "high scores I/O error: {}", error),
+            HighScoresError::Format { line, message } => write!(f, "malformed high scores at line {}: {}", line, message),
+        }
+    }
+}
+
+impl Error for HighScoresError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            HighScoresError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for HighScoresError {
+    fn from(error: io::Error) -> Self {
+        HighScoresError::Io(error)
+    }
+}
+
+/// Result of a single finished game.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HighScoreEntry {
+    /// Name of the player.
+    pub player: String,
+    /// Score collected during the game.
+    pub score: usize,
+    /// Length of the snake at the end of the game.
+    pub length: usize,
+    /// Board width and height.
+    pub board_size: (usize, usize),
+    /// Short description of the played mode, e.g. `classic`, `wrap` or a level name.
+    pub mode: String,
+    /// Game time in seconds, counted in ticks.
+    pub duration_secs: u64,
+    /// Seconds since the Unix epoch when the game has ended.
+    pub date: u64,
+}
+
+impl HighScoreEntry {
+    /// Creates an entry describing a game which has just ended.
+    pub fn from_game(game_logic: &GameLogic, player: &str, mode: &str) -> HighScoreEntry {
+        HighScoreEntry {
+            player: String::from(player),
+            score: game_logic.get_score(),
+            length: game_logic.get_length(),
+            board_size: game_logic.get_board_size(),
+            mode: String::from(mode),
+            duration_secs: game_logic.get_play_time().as_secs(),
+            date: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs()),
+        }
+    }
+
+    // orders better games first, older games first among equal ones
+    fn is_better_than(&self, other: &HighScoreEntry) -> bool {
+        (self.score, self.length) > (other.score, other.length) ||
+            ((self.score, self.length) == (other.score, other.length) && self.date < other.date)
+    }
+}
+
+/// Best games, sorted from the best one.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HighScores {
+    #[serde(skip, default = "default_capacity")]
+    capacity: usize,
+    #[serde(default)]
+    entries: Vec<HighScoreEntry>,
+}
+
+fn default_capacity() -> usize {
+    DEFAULT_CAPACITY
+}
+
+impl Default for HighScores {
+    fn default() -> Self {
+        HighScores::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl HighScores {
+    /// Creates an empty table keeping at most `capacity` games.
+    pub fn new(capacity: usize) -> HighScores {
+        HighScores {
+            capacity,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Returns the path of the high-score file in the user's data directory.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|directory| directory.join("snake2d").join(HIGH_SCORES_FILE_NAME))
+    }
+
+    /// Loads the table from a file, returning an empty table if the file does not exist. Games
+    /// beyond `capacity` are dropped.
+    pub fn load<P: AsRef<Path>>(path: P, capacity: usize) -> Result<HighScores, HighScoresError> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(HighScores::new(capacity)),
+            Err(error) => return Err(HighScoresError::Io(error)),
+        };
+        HighScores::parse_with_capacity(&text, capacity)
+    }
+
+    /// Saves the table to a file, creating its directory if needed.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), HighScoresError> {
+        if let Some(directory) = path.as_ref().parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    /// Parses the table in the format described in the module documentation, keeping at most
+    /// `DEFAULT_CAPACITY` games.
+    pub fn parse(text: &str) -> Result<HighScores, HighScoresError> {
+        HighScores::parse_with_capacity(text, DEFAULT_CAPACITY)
+    }
+
+    fn parse_with_capacity(text: &str, capacity: usize) -> Result<HighScores, HighScoresError> {
+        let mut high_scores: HighScores = toml::from_str(text).map_err(|error| {
+            let line = error.span().map_or(0, |span| text[..span.start].matches('\n').count() + 1);
+            HighScoresError::Format { line, message: String::from(error.message()) }
+        })?;
+        high_scores.capacity = capacity;
+        let entries = std::mem::take(&mut high_scores.entries);
+        for entry in entries {
+            high_scores.insert(entry);
+        }
+        Ok(high_scores)
+    }
+
+    /// Adds a game to the table stored in a file, saving the file if the game is good enough to be
+    /// kept. Returns the rank of the game and the updated table.
+    pub fn record<P: AsRef<Path>>(path: P, capacity: usize, entry: HighScoreEntry)
+                                  -> Result<(Option<usize>, HighScores), HighScoresError> {
+        let mut high_scores = HighScores::load(&path, capacity)?;
+        let rank = high_scores.insert(entry);
+        if rank.is_some() {
+            high_scores.save(&path)?;
+        }
+        Ok((rank, high_scores))
+    }
+
+    /// Loads the table from the default path, describing a failure with a message.
+    pub fn load_default(capacity: usize) -> Result<HighScores, String> {
+        let path = HighScores::default_path()
+            .ok_or_else(|| String::from("Could not find the data directory for high scores"))?;
+        HighScores::load(&path, capacity)
+            .map_err(|error| format!("Could not load high scores {}: {}", path.display(), error))
+    }
+
+    /// Adds a game to the table stored at the default path. Returns the rank of the game and lines
+    /// describing the table headed by the rank, or a line describing why the game was not recorded.
+    pub fn record_default(capacity: usize, entry: HighScoreEntry) -> (Option<usize>, Vec<String>) {
+        let path = match HighScores::default_path() {
+            Some(path) => path,
+            None => return (None, vec![String::from("Could not find the data directory for high scores")]),
+        };
+        match HighScores::record(&path, capacity, entry) {
+            Ok((rank, high_scores)) => {
+                let mut lines = vec![match rank {
+                    Some(rank) => format!("High score #{}!", rank),
+                    None => String::from("High scores:"),
+                }];
+                lines.extend(high_scores.table_lines());
+                (rank, lines)
+            },
+            Err(error) => (None, vec![format!("Could not save high scores {}: {}", path.display(), error)]),
+        }
+    }
+
+    /// Returns the games in the table, the best one first.
+    pub fn entries(&self) -> &[HighScoreEntry] {
+        &self.entries
+    }
+
+    /// Adds a game to the table. Returns its rank counted from 1, or `None` if the game is not
+    /// good enough to be kept.
+    pub fn insert(&mut self, entry: HighScoreEntry) -> Option<usize> {
+        let index = self.entries.iter().position(|other| entry.is_better_than(other)).unwrap_or(self.entries.len());
+        if index >= self.capacity {
+            return None;
+        }
+        self.entries.insert(index, entry);
+        self.entries.truncate(self.capacity);
+        Some(index + 1)
+    }
+
+    /// Formats the table as lines of text, with a header line.
+    pub fn table_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("{:>3} {:<12} {:>6} {:>6} {:>7} {:<10} {:>8} {}",
+                                     "#", "Player", "Score", "Length", "Board", "Mode", "Time", "Date")];
+        for (index, entry) in self.entries.iter().enumerate() {
+            let board = format!("{}x{}", entry.board_size.0, entry.board_size.1);
+            let time = format!("{}:{:02}", entry.duration_secs / 60, entry.duration_secs % 60);
+            lines.push(format!("{:>3} {:<12} {:>6} {:>6} {:>7} {:<10} {:>8} {:<10}", index + 1, entry.player,
+                               entry.score, entry.length, board, entry.mode, time, format_date(entry.date)));
+        }
+        if self.entries.is_empty() {
+            lines.push(String::from("No games played yet."));
+        }
+        lines
+    }
+}
+
+impl fmt::Display for HighScores {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", toml::to_string(self).map_err(|_| fmt::Error)?)
+    }
+}
+
+/// Returns the name of the player running the game, taken from the environment.
+pub fn default_player_name() -> String {
+    std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_else(|_| String::from("player"))
+}
+
+// formats seconds since the Unix epoch as a YYYY-MM-DD date in UTC
+fn format_date(secs: u64) -> String {
+    // days to civil date, from Howard Hinnant's date algorithms
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Direction, Segment};
+
+    fn entry(player: &str, score: usize, date: u64) -> HighScoreEntry {
+        HighScoreEntry {
+            player: String::from(player),
+            score,
+            length: score / 10 + 1,
+            board_size: (25, 25),
+            mode: String::from("classic"),
+            duration_secs: 75,
+            date,
+        }
+    }
+
+    #[test]
+    fn given_games_when_inserted_then_table_keeps_best_ones_in_order() {
+        let mut high_scores = HighScores::new(3);
+
+        assert_eq!(high_scores.insert(entry("a", 20, 1)), Some(1));
+        assert_eq!(high_scores.insert(entry("b", 50, 2)), Some(1));
+        assert_eq!(high_scores.insert(entry("c", 20, 3)), Some(3));
+        assert_eq!(high_scores.insert(entry("d", 10, 4)), None);
+        assert_eq!(high_scores.insert(entry("e", 30, 5)), Some(2));
+
+        let players: Vec<&str> = high_scores.entries().iter().map(|entry| entry.player.as_str()).collect();
+        assert_eq!(players, vec!["b", "e", "a"]);
+    }
+
+    #[test]
+    fn given_table_when_written_and_parsed_then_table_is_the_same() {
+        let mut high_scores = HighScores::default();
+        high_scores.insert(entry("marcin", 120, 1_700_000_000));
+        high_scores.insert(entry("ola", 40, 1_700_000_100));
+        let text = high_scores.to_string();
+
+        assert!(text.starts_with("[[entries]]\nplayer = \"marcin\"\nscore = 120\n"));
+        assert_eq!(HighScores::parse(&text).unwrap(), high_scores);
+    }
+
+    #[test]
+    fn given_unsorted_file_when_parsed_then_entries_are_sorted() {
+        let text = "[[entries]]\nplayer = \"a\"\nscore = 1\nlength = 2\nboard_size = [5, 5]\nmode = \"wrap\"\n\
+                    duration_secs = 3\ndate = 4\n\n[[entries]]\nplayer = \"b\"\nscore = 9\nlength = 2\n\
+                    board_size = [5, 5]\nmode = \"wrap\"\nduration_secs = 3\ndate = 4\n";
+        let high_scores = HighScores::parse(text).unwrap();
+        assert_eq!(high_scores.entries()[0].player, "b");
+    }
+
+    #[test]
+    fn given_malformed_file_when_parsed_then_error_points_to_line() {
+        match HighScores::parse("[[entries]]\nplayer = \"a\"\nscore = \"many\"\n") {
+            Err(HighScoresError::Format { line: 3, .. }) => (),
+            other => panic!("Expected format error at line 3, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn given_table_when_saved_and_loaded_then_table_is_the_same() {
+        let path = std::env::temp_dir().join(format!("snake2d-scores-test-{}", std::process::id())).join("scores.toml");
+        let mut high_scores = HighScores::new(5);
+        high_scores.insert(entry("a", 10, 1));
+        high_scores.insert(entry("b", 20, 2));
+        high_scores.save(&path).unwrap();
+
+        let loaded = HighScores::load(&path, 5).unwrap();
+        let truncated = HighScores::load(&path, 1).unwrap();
+        let (rank, recorded) = HighScores::record(&path, 5, entry("c", 15, 3)).unwrap();
+        let reloaded = HighScores::load(&path, 5).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert_eq!(loaded, high_scores);
+        assert_eq!(truncated.entries().len(), 1);
+        assert_eq!(rank, Some(2));
+        assert_eq!(recorded.entries().len(), 3);
+        assert_eq!(reloaded, recorded);
+        assert_eq!(HighScores::load(&path, 5).unwrap(), HighScores::new(5));
+    }
+
+    #[test]
+    fn given_table_then_lines_describe_games() {
+        let mut high_scores = HighScores::default();
+        assert_eq!(high_scores.table_lines()[1], "No games played yet.");
+
+        high_scores.insert(entry("marcin", 120, 1_700_000_000));
+        let lines = high_scores.table_lines();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1], "  1 marcin          120     13   25x25 classic        1:15 2023-11-14");
+    }
+
+    #[test]
+    fn given_dates_then_they_are_formatted_in_utc() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_704_067_199), "2023-12-31");
+    }
+
+    #[test]
+    fn given_finished_game_then_entry_describes_it() {
+        let mut game_logic = GameLogic::with_seed(8, 6, Segment::new(0, 0), 0);
+        for _ in 0..30 {
+            game_logic.step(Some(Direction::Left));
+        }
+        let entry = HighScoreEntry::from_game(&game_logic, "marcin", "classic");
+
+        assert_eq!(entry.score, 0);
+        assert_eq!(entry.length, 1);
+        assert_eq!(entry.board_size, (8, 6));
+        assert_eq!(entry.duration_secs, 0);
+        assert!(entry.date > 1_700_000_000);
+    }
+}
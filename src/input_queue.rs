@@ -0,0 +1,120 @@
+//! Buffering of direction changes between game ticks.
+
+use std::collections::VecDeque;
+
+use crate::Direction;
+
+/// Number of turns buffered by default, enough for a tight U-turn and one more move.
+pub const INPUT_QUEUE_CAPACITY: usize = 3;
+
+/// Bounded queue of turns requested by the player, one of them is applied per tick.
+///
+/// Every turn is checked against the direction the snake will have once the turns queued before
+/// it are applied, so reversals and repeated presses of the same key never take up a tick.
+#[derive(Clone, Debug)]
+pub struct InputQueue {
+    capacity: usize,
+    turns: VecDeque<Direction>,
+}
+
+impl InputQueue {
+    /// Creates an empty queue holding at most `capacity` turns.
+    pub fn new(capacity: usize) -> InputQueue {
+        assert!(capacity > 0, "Input queue must hold at least one turn!");
+        InputQueue { capacity, turns: VecDeque::with_capacity(capacity) }
+    }
+
+    /// Queues a turn, `current` is the direction the snake moves in now. Returns false if the
+    /// turn was dropped because it reverses or repeats the previous direction, or the queue is full.
+    pub fn push(&mut self, turn: Direction, current: Option<Direction>) -> bool {
+        if let Some(previous) = self.turns.back().copied().or(current) {
+            if turn == previous || turn == previous.opposite() {
+                return false;
+            }
+        }
+        if self.turns.len() == self.capacity {
+            return false;
+        }
+        self.turns.push_back(turn);
+        true
+    }
+
+    /// Takes the turn to be applied in the next tick.
+    pub fn pop(&mut self) -> Option<Direction> {
+        self.turns.pop_front()
+    }
+
+    /// Drops all queued turns.
+    pub fn clear(&mut self) {
+        self.turns.clear();
+    }
+
+    /// Returns the number of queued turns.
+    pub fn len(&self) -> usize {
+        self.turns.len()
+    }
+
+    /// Returns true if no turn is queued.
+    pub fn is_empty(&self) -> bool {
+        self.turns.is_empty()
+    }
+}
+
+impl Default for InputQueue {
+    fn default() -> Self {
+        InputQueue::new(INPUT_QUEUE_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_two_turns_when_popped_then_turns_come_in_order() {
+        let mut queue = InputQueue::default();
+        assert!(queue.push(Direction::Up, Some(Direction::Right)));
+        assert!(queue.push(Direction::Left, Some(Direction::Right)));
+
+        assert_eq!(queue.pop(), Some(Direction::Up));
+        assert_eq!(queue.pop(), Some(Direction::Left));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn given_reversal_when_pushed_then_turn_is_dropped() {
+        let mut queue = InputQueue::default();
+        assert!(!queue.push(Direction::Left, Some(Direction::Right)));
+        assert!(queue.push(Direction::Up, Some(Direction::Right)));
+        assert!(!queue.push(Direction::Down, Some(Direction::Right)));
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[test]
+    fn given_repeated_direction_when_pushed_then_turn_is_dropped() {
+        let mut queue = InputQueue::default();
+        assert!(!queue.push(Direction::Right, Some(Direction::Right)));
+        assert!(queue.push(Direction::Up, Some(Direction::Right)));
+        assert!(!queue.push(Direction::Up, Some(Direction::Right)));
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[test]
+    fn given_no_direction_when_pushed_then_any_turn_is_queued() {
+        let mut queue = InputQueue::default();
+        assert!(queue.push(Direction::Down, None));
+        assert_eq!(queue.pop(), Some(Direction::Down));
+    }
+
+    #[test]
+    fn given_full_queue_when_pushed_then_turn_is_dropped() {
+        let mut queue = InputQueue::new(2);
+        assert!(queue.push(Direction::Up, Some(Direction::Right)));
+        assert!(queue.push(Direction::Left, Some(Direction::Right)));
+        assert!(!queue.push(Direction::Down, Some(Direction::Right)));
+        assert_eq!(queue.len(), 2);
+
+        queue.clear();
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,158 @@
+//! Mapping of keys to game actions.
+//!
+//! Keys are named independently of the window or terminal library: letters and digits by
+//! themselves (`W`, `7`, case does not matter) and other keys by `Up`, `Down`, `Left`, `Right`,
+//! `Space`, `Enter`, `Escape`, `Tab` and `Backspace`.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Direction;
+
+/// Names of keys other than letters and digits.
+const NAMED_KEYS: [&str; 9] = ["Up", "Down", "Left", "Right", "Space", "Enter", "Escape", "Tab", "Backspace"];
+
+/// Action triggered by a key.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    /// Turns the snake.
+    Move(Direction),
+    /// Pauses a running game or resumes a paused one.
+    Pause,
+    /// Advances a paused game by a single tick.
+    Step,
+    /// Starts a new game.
+    Restart,
+    /// Closes the game.
+    Quit,
+}
+
+/// Set of movement keys the bindings start from. Arrow keys move the snake in every preset.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyPreset {
+    /// Arrow keys only.
+    #[default]
+    Arrows,
+    /// Arrow keys and `W`, `A`, `S`, `D`.
+    Wasd,
+    /// Arrow keys and `H`, `J`, `K`, `L` as in vim.
+    Vim,
+}
+
+/// Returns the canonical name of a key, or `None` if the name is unknown.
+pub fn normalize_key_name(name: &str) -> Option<String> {
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphanumeric() => Some(c.to_ascii_uppercase().to_string()),
+        _ => NAMED_KEYS.iter().find(|named| named.eq_ignore_ascii_case(name)).map(|named| named.to_string()),
+    }
+}
+
+/// Keys bound to actions, every key triggers at most one action.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyBindings {
+    actions: BTreeMap<String, Action>,
+}
+
+impl KeyBindings {
+    /// Creates bindings of a preset, with `P` to pause, `N` to step, `R` to restart and `Q` or
+    /// `Escape` to quit.
+    pub fn preset(preset: KeyPreset) -> KeyBindings {
+        let mut bindings = KeyBindings { actions: BTreeMap::new() };
+        let moves = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+        let letters = match preset {
+            KeyPreset::Arrows => None,
+            KeyPreset::Wasd => Some(["W", "S", "A", "D"]),
+            KeyPreset::Vim => Some(["K", "J", "H", "L"]),
+        };
+        for (index, &direction) in moves.iter().enumerate() {
+            bindings.actions.insert(NAMED_KEYS[index].to_string(), Action::Move(direction));
+            if let Some(letters) = letters {
+                bindings.actions.insert(letters[index].to_string(), Action::Move(direction));
+            }
+        }
+        for &(key, action) in [("P", Action::Pause), ("N", Action::Step), ("R", Action::Restart),
+                ("Q", Action::Quit), ("Escape", Action::Quit)].iter() {
+            bindings.actions.insert(key.to_string(), action);
+        }
+        bindings
+    }
+
+    /// Binds a key to an action. Returns an error if the key name is unknown or the key is already
+    /// bound to another action.
+    pub fn bind(&mut self, key: &str, action: Action) -> Result<(), String> {
+        let name = normalize_key_name(key).ok_or_else(|| format!("unknown key '{}'", key))?;
+        match self.actions.get(&name) {
+            Some(&bound) if bound != action => Err(format!("key '{}' is already bound to {:?}", name, bound)),
+            _ => {
+                self.actions.insert(name, action);
+                Ok(())
+            },
+        }
+    }
+
+    /// Returns the action bound to a key.
+    pub fn action(&self, key: &str) -> Option<Action> {
+        normalize_key_name(key).and_then(|name| self.actions.get(&name).copied())
+    }
+
+    /// Returns the keys bound to an action, in alphabetical order.
+    pub fn keys(&self, action: Action) -> Vec<&str> {
+        self.actions.iter().filter(|(_, &bound)| bound == action).map(|(key, _)| key.as_str()).collect()
+    }
+
+    /// Describes the keys bound to an action for help texts, e.g. `Escape/Q`.
+    pub fn describe(&self, action: Action) -> String {
+        self.keys(action).join("/")
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings::preset(KeyPreset::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_key_names_when_normalized_then_case_does_not_matter() {
+        assert_eq!(normalize_key_name("w"), Some(String::from("W")));
+        assert_eq!(normalize_key_name("5"), Some(String::from("5")));
+        assert_eq!(normalize_key_name("escape"), Some(String::from("Escape")));
+        assert_eq!(normalize_key_name("F13"), None);
+        assert_eq!(normalize_key_name("!"), None);
+        assert_eq!(normalize_key_name(""), None);
+    }
+
+    #[test]
+    fn given_presets_when_created_then_arrows_and_letters_move_snake() {
+        let wasd = KeyBindings::preset(KeyPreset::Wasd);
+        assert_eq!(wasd.action("a"), Some(Action::Move(Direction::Left)));
+        assert_eq!(wasd.action("Left"), Some(Action::Move(Direction::Left)));
+        assert_eq!(wasd.keys(Action::Move(Direction::Up)), vec!["Up", "W"]);
+
+        let vim = KeyBindings::preset(KeyPreset::Vim);
+        assert_eq!(vim.action("j"), Some(Action::Move(Direction::Down)));
+        assert_eq!(vim.action("K"), Some(Action::Move(Direction::Up)));
+        assert_eq!(vim.describe(Action::Quit), "Escape/Q");
+
+        let arrows = KeyBindings::default();
+        assert_eq!(arrows.action("W"), None);
+        assert_eq!(arrows.action("P"), Some(Action::Pause));
+    }
+
+    #[test]
+    fn given_bound_key_when_bound_to_other_action_then_error_is_returned() {
+        let mut bindings = KeyBindings::default();
+        assert!(bindings.bind("space", Action::Pause).is_ok());
+        assert!(bindings.bind("P", Action::Pause).is_ok());
+        assert!(bindings.bind("p", Action::Restart).is_err());
+        assert!(bindings.bind("F1", Action::Restart).is_err());
+        assert_eq!(bindings.describe(Action::Pause), "P/Space");
+    }
+}
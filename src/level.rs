@@ -0,0 +1,527 @@
+//! Hand-designed maps.
+//!
+//! A level file describes the board, the snake's starting position and optionally fruit placed
+//! on the board before the game starts:
+//!
+//! ```text
+//! // a small arena
+//! snake2d-level 1
+//! snake 3,2 2,2 1,2
+//! direction right
+//! topology bounded
+//! map
+//! ##########
+//! #........#
+//! #......2.#
+//! #...##...#
+//! ##########
+//! ```
+//!
+//! The header starts with the format version followed by fields, one per line:
+//! * `snake` - snake segments starting with the head, each segment next to the previous one,
+//! * `direction` - `up`, `down`, `left` or `right`, the snake moves this way from the first tick.
+//!   Required if the snake has more than one segment, otherwise the snake waits for a key press,
+//! * `topology` - `bounded` (default) or `toroidal`.
+//!
+//! Empty lines and lines starting with `//` are ignored in the header. Every line after `map` is
+//! a row of the board: `.` is an empty field, `#` a wall and a digit from `1` to `9` a fruit worth
+//! that many points. All rows must have the same length.
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::game_logic::GameLogic;
+use crate::{Direction, Segment, Topology};
+
+/// Version of the level file format written by this build.
+pub const LEVEL_FORMAT_VERSION: u32 = 1;
+
+const LEVEL_MAGIC: &str = "snake2d-level";
+
+/// Error returned when a level cannot be loaded.
+#[derive(Debug)]
+pub enum LevelError {
+    /// Reading the level file has failed.
+    Io(io::Error),
+    /// Level file was written in a format version this build does not understand.
+    UnsupportedVersion(u32),
+    /// Level file is malformed.
+    Format {
+        /// Line (counted from 1) where the problem was found, 0 if the problem concerns the
+        /// level as a whole.
+        line: usize,
+        /// Description of the problem.
+        message: String,
+    },
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LevelError::Io(error) => write!(f, "level I/O error: {}", error),
+            LevelError::UnsupportedVersion(version) =>
+                write!(f, "unsupported level format version {} (expected {})", version, LEVEL_FORMAT_VERSION),
+            LevelError::Format { line, message } => write!(f, "malformed level at line {}: {}", line, message),
+        }
+    }
+}
+
+impl Error for LevelError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LevelError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LevelError {
+    fn from(error: io::Error) -> Self {
+        LevelError::Io(error)
+    }
+}
+
+/// Initial state of a game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Level {
+    /// Board width.
+    pub width: usize,
+    /// Board height.
+    pub height: usize,
+    /// Shape of the board.
+    pub topology: Topology,
+    /// Positions of walls.
+    pub walls: Vec<(usize, usize)>,
+    /// Snake segments, starting with the head.
+    pub snake: Vec<Segment>,
+    /// Direction the snake moves from the first tick, `None` waits for the player.
+    pub direction: Option<Direction>,
+    /// Fruit placed before the game starts, with their points.
+    pub fruit: Vec<(usize, usize, u8)>,
+}
+
+impl Level {
+    /// Creates an empty bounded level with a one segment snake waiting for the player.
+    pub fn empty(width: usize, height: usize, starting_segment: Segment) -> Level {
+        Level {
+            width,
+            height,
+            topology: Topology::Bounded,
+            walls: Vec::new(),
+            snake: vec![starting_segment],
+            direction: None,
+            fruit: Vec::new(),
+        }
+    }
+
+    /// Loads a level from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Level, LevelError> {
+        Level::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Creates a new game starting in this level.
+    pub fn new_game(&self, seed: u64) -> GameLogic {
+        GameLogic::from_level(self, seed)
+    }
+
+    /// Parses a level in the text format described in the module documentation.
+    pub fn parse(text: &str) -> Result<Level, LevelError> {
+        let mut lines = text.lines().enumerate().map(|(index, line)| (index + 1, line));
+        let mut version = None;
+        let mut snake = None;
+        let mut direction = None;
+        let mut topology = Topology::Bounded;
+        let mut map_line = None;
+
+        for (number, line) in &mut lines {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            let key = words.next().unwrap();
+            let values: Vec<&str> = words.collect();
+            if version.is_none() {
+                if key != LEVEL_MAGIC {
+                    return Err(format_error(number, "not a snake2d level file"));
+                }
+                match values.first().map(|value| value.parse::<u32>()) {
+                    Some(Ok(parsed)) if values.len() == 1 => {
+                        if parsed == 0 || parsed > LEVEL_FORMAT_VERSION {
+                            return Err(LevelError::UnsupportedVersion(parsed));
+                        }
+                        version = Some(parsed);
+                    },
+                    _ => return Err(format_error(number, "missing level format version")),
+                }
+                continue;
+            }
+            match key {
+                "snake" => snake = Some(parse_segments(number, &values)?),
+                "direction" => direction = Some(parse_direction(number, &single_value(number, key, &values)?)?),
+                "topology" => topology = parse_topology(number, &single_value(number, key, &values)?)?,
+                "map" if values.is_empty() => {
+                    map_line = Some(number);
+                    break;
+                },
+                _ => return Err(format_error(number, &format!("unknown field '{}'", line))),
+            }
+        }
+
+        if version.is_none() {
+            return Err(format_error(1, "not a snake2d level file"));
+        }
+        let map_line = map_line.ok_or_else(|| format_error(0, "missing 'map' section"))?;
+        let snake = snake.ok_or_else(|| format_error(0, "missing 'snake' field"))?;
+
+        let mut rows: Vec<(usize, &str)> = lines.map(|(number, line)| (number, line.trim_end())).collect();
+        while rows.last().is_some_and(|(_, row)| row.is_empty()) {
+            rows.pop();
+        }
+        if rows.is_empty() {
+            return Err(format_error(map_line, "map has no rows"));
+        }
+        let width = rows[0].1.chars().count();
+        let height = rows.len();
+        let mut walls = Vec::new();
+        let mut fruit = Vec::new();
+        for (y, &(number, row)) in rows.iter().enumerate() {
+            if row.chars().count() != width {
+                return Err(format_error(number, &format!("row has {} fields, expected {}", row.chars().count(), width)));
+            }
+            for (x, field) in row.chars().enumerate() {
+                match field {
+                    '.' => (),
+                    '#' => walls.push((x, y)),
+                    '1'..='9' => fruit.push((x, y, field as u8 - b'0')),
+                    _ => return Err(format_error(number, &format!("unknown field '{}'", field))),
+                }
+            }
+        }
+        if width == 0 {
+            return Err(format_error(map_line + 1, "map has no columns"));
+        }
+
+        let level = Level {
+            width,
+            height,
+            topology,
+            walls,
+            snake,
+            direction,
+            fruit,
+        };
+        level.validate().map_err(|message| format_error(0, &message))?;
+        Ok(level)
+    }
+
+    /// Checks that walls, fruit and the snake fit the board and do not overlap each other.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.width == 0 || self.height == 0 {
+            return Err(String::from("board size must be positive"));
+        }
+        let mut walls = HashSet::new();
+        for &(x, y) in self.walls.iter() {
+            if x >= self.width || y >= self.height {
+                return Err(format!("wall ({}, {}) is outside of the board", x, y));
+            }
+            if !walls.insert((x, y)) {
+                return Err(format!("wall ({}, {}) is repeated", x, y));
+            }
+        }
+        let mut fruit = HashSet::new();
+        for &(x, y, points) in self.fruit.iter() {
+            if x >= self.width || y >= self.height {
+                return Err(format!("fruit ({}, {}) is outside of the board", x, y));
+            }
+            if points == 0 {
+                return Err(format!("fruit ({}, {}) is worth no points", x, y));
+            }
+            if walls.contains(&(x, y)) || !fruit.insert((x, y)) {
+                return Err(format!("fruit ({}, {}) is not on an empty field", x, y));
+            }
+        }
+        if self.snake.is_empty() {
+            return Err(String::from("snake has no segments"));
+        }
+        let in_board = |segment: &Segment| segment.x >= 0 && segment.y >= 0 &&
+            (segment.x as usize) < self.width && (segment.y as usize) < self.height;
+        let mut snake = HashSet::new();
+        for (index, segment) in self.snake.iter().enumerate() {
+            if !in_board(segment) {
+                return Err(format!("snake segment ({}, {}) is outside of the board", segment.x, segment.y));
+            }
+            let field = (segment.x as usize, segment.y as usize);
+            if walls.contains(&field) || fruit.contains(&field) {
+                return Err(format!("snake segment ({}, {}) is not on an empty field", segment.x, segment.y));
+            }
+            if !snake.insert(field) {
+                return Err(format!("snake segment ({}, {}) is repeated", segment.x, segment.y));
+            }
+            if index > 0 && !self.are_neighbours(&self.snake[index - 1], segment) {
+                return Err(format!("snake segment ({}, {}) is not next to the previous one", segment.x, segment.y));
+            }
+        }
+        if self.snake.len() > 1 {
+            match self.direction {
+                None => return Err(String::from("'direction' is required for a snake longer than one segment")),
+                Some(direction) => if self.neighbour(&self.snake[0], &direction) == self.snake[1] {
+                    return Err(String::from("snake cannot start moving backwards"));
+                },
+            }
+        }
+        Ok(())
+    }
+
+    fn neighbour(&self, segment: &Segment, direction: &Direction) -> Segment {
+        match self.topology {
+            Topology::Bounded => direction.nearest_segment(segment),
+            Topology::Toroidal => direction.nearest_segment_wrapped(segment, self.width, self.height),
+        }
+    }
+
+    fn are_neighbours(&self, first: &Segment, second: &Segment) -> bool {
+        [Direction::Up, Direction::Down, Direction::Left, Direction::Right].iter()
+            .any(|direction| &self.neighbour(first, direction) == second)
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} {}", LEVEL_MAGIC, LEVEL_FORMAT_VERSION)?;
+        let segments: Vec<String> = self.snake.iter().map(|segment| format!("{},{}", segment.x, segment.y)).collect();
+        writeln!(f, "snake {}", segments.join(" "))?;
+        if let Some(direction) = self.direction {
+            writeln!(f, "direction {}", direction_to_str(direction))?;
+        }
+        writeln!(f, "topology {}", topology_to_str(self.topology))?;
+        writeln!(f, "map")?;
+        let mut rows = vec![vec!['.'; self.width]; self.height];
+        for &(x, y) in self.walls.iter() {
+            rows[y][x] = '#';
+        }
+        for &(x, y, points) in self.fruit.iter() {
+            rows[y][x] = (b'0' + points) as char;
+        }
+        for row in rows {
+            writeln!(f, "{}", row.into_iter().collect::<String>())?;
+        }
+        Ok(())
+    }
+}
+
+fn format_error(line: usize, message: &str) -> LevelError {
+    LevelError::Format { line, message: String::from(message) }
+}
+
+fn single_value(number: usize, key: &str, values: &[&str]) -> Result<String, LevelError> {
+    match values {
+        [value] => Ok(value.to_lowercase()),
+        _ => Err(format_error(number, &format!("'{}' field expects 1 value", key))),
+    }
+}
+
+fn parse_segments(number: usize, values: &[&str]) -> Result<Vec<Segment>, LevelError> {
+    if values.is_empty() {
+        return Err(format_error(number, "'snake' field expects at least 1 segment"));
+    }
+    values.iter().map(|value| {
+        let coords: Vec<Result<i32, _>> = value.split(',').map(str::parse::<i32>).collect();
+        match coords.as_slice() {
+            [Ok(x), Ok(y)] => Ok(Segment::new(*x, *y)),
+            _ => Err(format_error(number, &format!("invalid segment '{}', expected x,y", value))),
+        }
+    }).collect()
+}
+
+/// Parses a direction name: `up`, `down`, `left` or `right`.
+pub fn str_to_direction(name: &str) -> Option<Direction> {
+    match name {
+        "up" => Some(Direction::Up),
+        "down" => Some(Direction::Down),
+        "left" => Some(Direction::Left),
+        "right" => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+fn parse_direction(number: usize, name: &str) -> Result<Direction, LevelError> {
+    str_to_direction(name).ok_or_else(|| format_error(number, &format!("unknown direction '{}'", name)))
+}
+
+fn direction_to_str(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Up => "up",
+        Direction::Down => "down",
+        Direction::Left => "left",
+        Direction::Right => "right",
+    }
+}
+
+/// Parses a topology name: `bounded` or `toroidal`.
+pub fn str_to_topology(name: &str) -> Option<Topology> {
+    match name {
+        "bounded" => Some(Topology::Bounded),
+        "toroidal" => Some(Topology::Toroidal),
+        _ => None,
+    }
+}
+
+/// Returns the name of a topology, as used in level and replay files.
+pub fn topology_to_str(topology: Topology) -> &'static str {
+    match topology {
+        Topology::Bounded => "bounded",
+        Topology::Toroidal => "toroidal",
+    }
+}
+
+fn parse_topology(number: usize, name: &str) -> Result<Topology, LevelError> {
+    str_to_topology(name).ok_or_else(|| format_error(number, &format!("unknown topology '{}'", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_logic::{GameOverCause, GameStatus};
+
+    const ARENA: &str = "// a small arena
+snake2d-level 1
+snake 3,2 2,2 1,2
+direction right
+
+map
+##########
+#........#
+#......2.#
+#...##...#
+##########
+";
+
+    fn assert_format_error(text: &str, expected_line: usize) {
+        match Level::parse(text) {
+            Err(LevelError::Format { line, .. }) => assert_eq!(line, expected_line),
+            other => panic!("Expected format error at line {}, got {:?}", expected_line, other),
+        }
+    }
+
+    #[test]
+    fn given_level_text_when_parsed_then_level_describes_the_map() {
+        let level = Level::parse(ARENA).unwrap();
+
+        assert_eq!((level.width, level.height), (10, 5));
+        assert_eq!(level.topology, Topology::Bounded);
+        assert_eq!(level.snake, vec![Segment::new(3, 2), Segment::new(2, 2), Segment::new(1, 2)]);
+        assert_eq!(level.direction, Some(Direction::Right));
+        assert_eq!(level.fruit, vec![(7, 2, 2)]);
+        assert_eq!(level.walls.len(), 10 + 10 + 3 * 2 + 2);
+        assert!(level.walls.contains(&(4, 3)));
+        assert!(level.walls.contains(&(5, 3)));
+    }
+
+    #[test]
+    fn given_level_when_written_and_parsed_then_level_is_the_same() {
+        let mut level = Level::parse(ARENA).unwrap();
+        level.topology = Topology::Toroidal;
+        assert_eq!(Level::parse(&level.to_string()).unwrap(), level);
+    }
+
+    #[test]
+    fn given_malformed_level_when_parsed_then_error_points_to_line() {
+        assert_format_error("snake 1,1\nmap\n...\n", 1);
+        assert_format_error("snake2d-level 1\nsnake 1,1\nspeed 3\nmap\n...\n", 3);
+        assert_format_error("snake2d-level 1\nsnake 1;1\nmap\n...\n", 2);
+        assert_format_error("snake2d-level 1\nsnake 0,0\ndirection north\nmap\n...\n", 3);
+        assert_format_error("snake2d-level 1\nsnake 0,0\nmap\n...\n..\n", 5);
+        assert_format_error("snake2d-level 1\nsnake 0,0\nmap\n..x\n", 4);
+        assert_format_error("snake2d-level 1\nsnake 0,0\nmap\n", 3);
+        assert_format_error("snake2d-level 1\nsnake 0,0\n", 0);
+        match Level::parse("snake2d-level 7\nsnake 0,0\nmap\n.\n") {
+            Err(LevelError::UnsupportedVersion(7)) => (),
+            other => panic!("Expected unsupported version error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn given_invalid_snake_when_parsed_then_error_is_returned() {
+        // outside of the board
+        assert_format_error("snake2d-level 1\nsnake 3,0\nmap\n...\n", 0);
+        // on a wall
+        assert_format_error("snake2d-level 1\nsnake 1,0\nmap\n.#.\n", 0);
+        // segments not next to each other
+        assert_format_error("snake2d-level 1\nsnake 0,0 2,0\ndirection left\nmap\n...\n", 0);
+        // no direction for a long snake
+        assert_format_error("snake2d-level 1\nsnake 1,0 0,0\nmap\n...\n", 0);
+        // moving backwards
+        assert_format_error("snake2d-level 1\nsnake 1,0 0,0\ndirection left\nmap\n...\n", 0);
+        // wrapped around a toroidal board
+        assert!(Level::parse("snake2d-level 1\nsnake 0,0 2,0\ndirection down\ntopology toroidal\nmap\n...\n").is_ok());
+    }
+
+    #[test]
+    fn given_invalid_walls_or_fruit_when_validated_then_error_is_returned() {
+        let level = Level::empty(3, 3, Segment::new(0, 0));
+        assert!(level.validate().is_ok());
+        // outside of the board
+        assert!(Level { walls: vec![(5, 5)], ..level.clone() }.validate().is_err());
+        assert!(Level { fruit: vec![(0, 3, 1)], ..level.clone() }.validate().is_err());
+        // repeated
+        assert!(Level { walls: vec![(1, 1), (1, 1)], ..level.clone() }.validate().is_err());
+        assert!(Level { fruit: vec![(1, 1, 1), (1, 1, 2)], ..level.clone() }.validate().is_err());
+        // fruit on a wall
+        assert!(Level { walls: vec![(1, 1)], fruit: vec![(1, 1, 1)], ..level.clone() }.validate().is_err());
+        // worth no points
+        assert!(Level { fruit: vec![(1, 1, 0)], ..level.clone() }.validate().is_err());
+        // repeated snake segment
+        let snake = vec![Segment::new(0, 0), Segment::new(1, 0), Segment::new(0, 0)];
+        assert_eq!(Level { snake, direction: Some(Direction::Down), ..level }.validate(),
+                   Err(String::from("snake segment (0, 0) is repeated")));
+    }
+
+    #[test]
+    fn given_large_level_when_validated_then_repeated_wall_is_found() {
+        let mut level = Level::empty(500, 500, Segment::new(0, 0));
+        level.walls = (0..500).flat_map(|y| (1..500).map(move |x| (x, y))).collect();
+        assert!(level.validate().is_ok());
+
+        level.walls.push((499, 499));
+        assert_eq!(level.validate(), Err(String::from("wall (499, 499) is repeated")));
+    }
+
+    #[test]
+    fn given_level_when_game_is_created_then_game_starts_in_level_state() {
+        let level = Level::parse(ARENA).unwrap();
+        let mut game_logic = level.new_game(0);
+
+        assert_eq!(game_logic.get_board_size(), (10, 5));
+        assert_eq!(game_logic.get_snake_segments().len(), 3);
+        assert_eq!(game_logic.get_obstacles(), vec![(7, 2, 2)]);
+        let mut walls = level.walls.clone();
+        walls.sort();
+        assert_eq!(game_logic.get_walls(), walls);
+
+        // snake keeps moving right until it hits the wall
+        for _ in 0..5 {
+            assert_eq!(game_logic.step(None).status, GameStatus::Running);
+        }
+        match game_logic.step(None).status {
+            GameStatus::GameOver { cause: GameOverCause::Wall, score } => assert!(score >= 5),
+            status => panic!("Expected game over by wall, got {:?}", status),
+        }
+    }
+
+    #[test]
+    fn given_bundled_levels_when_loaded_then_levels_are_valid() {
+        let pattern = Path::new(env!("CARGO_MANIFEST_DIR")).join("levels").join("*.txt");
+        let paths: Vec<_> = glob::glob(pattern.to_str().unwrap()).unwrap().flatten().collect();
+        assert!(!paths.is_empty());
+        for path in paths {
+            if let Err(error) = Level::load(&path) {
+                panic!("Invalid level {}: {}", path.display(), error);
+            }
+        }
+    }
+}
@@ -6,10 +6,27 @@ pub type Segment = Point2D<i32, ScreenSpace>;
 mod snake;
 mod board;
 pub mod game_logic;
+pub mod score;
+pub mod speed;
+pub mod renderer;
 pub mod game_view;
+pub mod tui_view;
+pub mod software_view;
 pub mod game_controller;
+pub mod input_queue;
+pub mod key_bindings;
+pub mod timestep;
+pub mod replay;
+pub mod level;
+pub mod headless;
+pub mod bot;
+pub mod tournament;
+pub mod environment;
+pub mod config;
+pub mod high_scores;
+pub mod cli;
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum Direction {
     Up,
     Down,
@@ -26,10 +43,55 @@ impl Direction {
             Direction::Right => Segment::new(segment.x + 1, segment.y),
         }
     }
+
+    /// Returns the direction pointing the other way.
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    /// Returns the direction after turning right, as seen by the snake.
+    pub fn turn_right(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    /// Returns the direction after turning left, as seen by the snake.
+    pub fn turn_left(&self) -> Direction {
+        self.turn_right().opposite()
+    }
+
+    /// Returns the nearest segment on a board whose opposite edges are connected, so leaving
+    /// the board on one edge re-enters it on the opposite one.
+    pub fn nearest_segment_wrapped(&self, segment: &Segment, width: usize, height: usize) -> Segment {
+        let nearest = self.nearest_segment(segment);
+        Segment::new(nearest.x.rem_euclid(width as i32), nearest.y.rem_euclid(height as i32))
+    }
 }
 
+/// Shape of the board.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Topology {
+    /// Board is surrounded by walls.
+    #[default]
+    Bounded,
+    /// Leaving the board on one edge re-enters it on the opposite edge.
+    Toroidal,
+}
+
+
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Obstacle {
     None,
     Apple(u8),
+    /// Impassable field, hitting it ends the game like hitting the border.
+    Wall,
 }
\ No newline at end of file
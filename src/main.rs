@@ -6,55 +6,111 @@
 use glutin_window::GlutinWindow as Window;
 use opengl_graphics::{GlGraphics, OpenGL};
 use piston::event_loop::{EventSettings, Events};
-use piston::input::{RenderEvent};
-use piston::window::{WindowSettings, AdvancedWindow};
+use piston::input::{Button, PressEvent, RenderEvent};
+use piston::window::{WindowSettings, AdvancedWindow, Window as _};
 
 use snake2d::*;
+use snake2d::cli::Options;
+use snake2d::high_scores::{HighScoreEntry, HighScores};
+use snake2d::key_bindings::Action;
+use snake2d::renderer::Renderer;
 
-fn dump_event_settings(events_settings: &EventSettings) {
-    println!("{}", events_settings.bench_mode);
-    println!("{}", events_settings.lazy);
-    println!("{}", events_settings.max_fps);
-    println!("{}", events_settings.swap_buffers);
-    println!("{}", events_settings.ups);
-    println!("{}", events_settings.ups_reset);
-}
+use std::process;
 
 fn main() {
+    let (options, config) = Options::from_args();
+    match options.run_command(&config) {
+        Ok(true) => return,
+        Ok(false) => (),
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(1);
+        },
+    }
+    let mut game_controller = options.game_controller(&config).unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        process::exit(1);
+    });
+    eprintln!("Seed: {}", game_controller.game_logic.get_seed());
+
     let opengl = OpenGL::V3_2;
     let settings =
-        WindowSettings::new("snake", [480, 480]).
+        WindowSettings::new("snake", config.window.size).
             graphics_api(opengl).
-            exit_on_esc(true);
+            exit_on_esc(false);
     let mut window:Window = settings.build().expect("Could not create window");
 
 
     let mut event_settings = EventSettings::new();
-    const FPS:u64= 15;
-    event_settings.ups = FPS;
-    event_settings.max_fps = FPS;
-    dump_event_settings(&event_settings);
+    // the game advances by the time elapsed in update events, at its own tick rate
+    event_settings.ups = config.window.fps;
+    event_settings.max_fps = config.window.fps;
     let mut events = Events::new(event_settings);
 
     let mut gl = GlGraphics::new(opengl);
 
-    const WIDTH: usize = 25;
-    const HEIGHT: usize = 25;
-    const STARTING_SEGMENT : Segment = Segment::new(5, 6);
-    let game_logic = game_logic::GameLogic::new(WIDTH, HEIGHT, STARTING_SEGMENT);
-    let mut game_controller = game_controller::GameController::new(game_logic);
-    let game_view_settings = game_view::GameViewSettings::new();
+    let game_view_settings = config.view.settings().expect("View settings are checked with the configuration!");
     let mut game_view = game_view::GameView::new(game_view_settings);
-    game_view.load_textures("assets");
+    game_view.load_textures(&config.window.assets);
 
+    let player = config.scores.player();
+    let mode = options.mode(&config);
+    let mut high_score_rank = None;
+    let mut high_score_lines = Vec::new();
+    let mut high_score_recorded = !options.records_high_scores();
     while let Some(e) = events.next(&mut window) {
+        if let Some(Button::Keyboard(key)) = e.press_args() {
+            if game_controller.key_action(key) == Some(Action::Quit) {
+                window.set_should_close(true);
+            }
+        }
         game_controller.event(&e);
-        window.set_title(String::from(format!("snake! Points: {}", game_controller.game_logic.get_points())));
+        if !game_controller.game_logic.get_status().is_over() {
+            // a restarted game gets its own high-score entry
+            high_score_rank = None;
+            high_score_lines.clear();
+            high_score_recorded = !options.records_high_scores();
+        }
+        if game_controller.game_logic.get_status().is_over() && !high_score_recorded {
+            let entry = HighScoreEntry::from_game(&game_controller.game_logic, &player, &mode);
+            let (rank, lines) = HighScores::record_default(config.scores.max_entries, entry);
+            high_score_rank = rank;
+            high_score_lines = lines;
+            high_score_recorded = true;
+        }
+        let game_logic = &game_controller.game_logic;
+        let mut title = match game_logic.get_status() {
+            game_logic::GameStatus::Running =>
+                format!("snake! Score: {} Length: {} Level: {}", game_logic.get_score(), game_logic.get_length(),
+                        game_logic.get_speed_level()),
+            game_logic::GameStatus::GameOver { cause, score } =>
+                format!("snake! Game over: {} Score: {} Length: {} Level: {}", cause, score,
+                        game_logic.get_length(), game_logic.get_speed_level()),
+            game_logic::GameStatus::Won { cause, score } =>
+                format!("snake! You won: {} Score: {} Length: {} Level: {}", cause, score,
+                        game_logic.get_length(), game_logic.get_speed_level()),
+        };
+        if game_controller.is_paused() {
+            let key_bindings = game_controller.key_bindings();
+            title.push_str(&format!(" Paused ({}: resume, {}: step, {}: restart)",
+                key_bindings.describe(Action::Pause), key_bindings.describe(Action::Step),
+                key_bindings.describe(Action::Restart)));
+        }
+        if let Some(rank) = high_score_rank {
+            title.push_str(&format!(" High score #{}!", rank));
+        }
+        window.set_title(title);
 
         if let Some(args) = e.render_args() {
+            let frame = game_controller.frame();
             gl.draw(args.viewport(), |c, g| {
-                game_view.draw(&game_controller, &c, g, args.window_size);
+                game_view::GlRenderer::new(&game_view, c, g, args.window_size).render(&frame);
+                if frame.status.is_over() {
+                    game_view.draw_text_lines(&high_score_lines, &c, g, args.window_size);
+                }
             });
         }
     }
+
+    options.save_recording(&game_controller);
 }
@@ -0,0 +1,153 @@
+//! Backend-agnostic description of what is drawn on the screen.
+
+use crate::game_logic::GameStatus;
+use crate::{Obstacle, Segment, Topology};
+
+/// Read-only snapshot of a game, containing everything needed to draw it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    /// Board width and height.
+    pub board_size: (usize, usize),
+    /// Shape of the board. Segments are always in board coordinates, also on a toroidal board.
+    pub topology: Topology,
+    /// Snake segments, starting with the head.
+    pub snake: Vec<Segment>,
+    /// Snake segments before the last tick, empty before the first tick.
+    pub previous_snake: Vec<Segment>,
+    /// Fraction of the time between the last and the next tick which has elapsed, renderers
+    /// drawing between ticks move the snake from `previous_snake` towards `snake` by it.
+    pub progress: f64,
+    /// Non-empty fields of the board.
+    pub obstacles: Vec<(usize, usize, Obstacle)>,
+    /// Score collected so far.
+    pub score: usize,
+    /// Length of the snake, including growth which is still pending.
+    pub length: usize,
+    /// Current combo multiplier, 1 if no combo is running.
+    pub combo: usize,
+    /// Speed level reached, starting from 1.
+    pub speed_level: usize,
+    /// Status of the game.
+    pub status: GameStatus,
+    /// True if the game is paused by the player.
+    pub paused: bool,
+    /// Number of steps played since the beginning of the game.
+    pub tick: usize,
+}
+
+impl Frame {
+    /// Returns the snake head, if the snake has any segments.
+    pub fn snake_head(&self) -> Option<Segment> {
+        self.snake.first().copied()
+    }
+
+    /// Returns positions of snake segments in fields, interpolated between the previous and the
+    /// current segments by the progress of the tick. Segments which jumped across the edge of a
+    /// toroidal board are not interpolated.
+    pub fn interpolated_snake(&self) -> Vec<(f64, f64)> {
+        self.snake.iter().enumerate().map(|(index, segment)| {
+            let previous = self.previous_snake.get(index).unwrap_or(segment);
+            if (segment.x - previous.x).abs() + (segment.y - previous.y).abs() > 1 {
+                return (segment.x as f64, segment.y as f64);
+            }
+            let lerp = |from: i32, to: i32| from as f64 + (to - from) as f64 * self.progress;
+            (lerp(previous.x, segment.x), lerp(previous.y, segment.y))
+        }).collect()
+    }
+
+    /// Returns true if the segment lies on the board.
+    pub fn segment_in(&self, segment: &Segment) -> bool {
+        segment.x >= 0 && segment.x < self.board_size.0 as i32 &&
+            segment.y >= 0 && segment.y < self.board_size.1 as i32
+    }
+}
+
+/// Draws frames of a game.
+pub trait Renderer {
+    /// Result of rendering a single frame.
+    type Output;
+
+    /// Draws a single frame.
+    fn render(&mut self, frame: &Frame) -> Self::Output;
+}
+
+/// Renderer which keeps all rendered frames in memory, to be inspected by tests.
+#[derive(Default)]
+pub struct RecordingRenderer {
+    /// Frames rendered so far.
+    pub frames: Vec<Frame>,
+}
+
+impl Renderer for RecordingRenderer {
+    type Output = ();
+
+    fn render(&mut self, frame: &Frame) {
+        self.frames.push(frame.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_logic::GameLogic;
+    use crate::Direction;
+
+    #[test]
+    fn given_game_when_frames_are_rendered_then_frames_describe_the_game() {
+        let mut game_logic = GameLogic::with_seed(10, 8, Segment::new(3, 3), 5);
+        let mut renderer = RecordingRenderer::default();
+        renderer.render(&game_logic.frame());
+        game_logic.step(Some(Direction::Down));
+        renderer.render(&game_logic.frame());
+
+        assert_eq!(renderer.frames.len(), 2);
+        let first = &renderer.frames[0];
+        assert_eq!(first.board_size, (10, 8));
+        assert_eq!(first.snake, vec![Segment::new(3, 3)]);
+        assert!(first.obstacles.is_empty());
+        assert_eq!(first.tick, 0);
+
+        let second = &renderer.frames[1];
+        assert_eq!(second.snake_head(), Some(Segment::new(3, 4)));
+        assert_eq!(second.score, 0);
+        assert_eq!(second.length, 1);
+        assert_eq!(second.status, GameStatus::Running);
+        assert_eq!(second.tick, 1);
+        let mut expected_obstacles: Vec<(usize, usize, Obstacle)> = game_logic.get_obstacles().into_iter()
+            .map(|(x, y, points)| (x, y, Obstacle::Apple(points)))
+            .collect();
+        expected_obstacles.sort_by_key(|&(x, y, _)| (x, y));
+        assert_eq!(second.obstacles, expected_obstacles);
+    }
+
+    #[test]
+    fn given_progress_when_interpolated_then_snake_lies_between_ticks() {
+        let mut game_logic = GameLogic::with_seed(10, 8, Segment::new(3, 3), 5);
+        assert_eq!(game_logic.frame().interpolated_snake(), vec![(3.0, 3.0)]);
+        game_logic.step(Some(Direction::Down));
+        let mut frame = game_logic.frame();
+        assert_eq!(frame.previous_snake, vec![Segment::new(3, 3)]);
+        assert_eq!(frame.progress, 1.0);
+        assert_eq!(frame.interpolated_snake(), vec![(3.0, 4.0)]);
+
+        frame.progress = 0.25;
+        assert_eq!(frame.interpolated_snake(), vec![(3.0, 3.25)]);
+    }
+
+    #[test]
+    fn given_wrapped_snake_when_interpolated_then_jumping_segment_is_not_interpolated() {
+        let mut game_logic = GameLogic::with_seed(4, 4, Segment::new(0, 1), 5).with_topology(Topology::Toroidal);
+        game_logic.step(Some(Direction::Left));
+        let mut frame = game_logic.frame();
+        frame.progress = 0.5;
+        assert_eq!(frame.interpolated_snake(), vec![(3.0, 1.0)]);
+    }
+
+    #[test]
+    fn given_frame_then_segment_in_checks_board_size() {
+        let frame = GameLogic::with_seed(4, 3, Segment::new(0, 0), 0).frame();
+        assert!(frame.segment_in(&Segment::new(3, 2)));
+        assert!(!frame.segment_in(&Segment::new(4, 2)));
+        assert!(!frame.segment_in(&Segment::new(0, -1)));
+    }
+}
@@ -0,0 +1,522 @@
+//! Recording and replaying of games.
+//!
+//! A replay stores everything needed to reproduce a game: the seed used to generate obstacles,
+//! the level the game started in and the move passed to `GameLogic::main_loop` on every tick.
+//! Replay files are plain text:
+//!
+//! ```text
+//! snake2d-replay 5
+//! seed 42
+//! level 7
+//! snake2d-level 1
+//! snake 1,1
+//! topology bounded
+//! map
+//! ....
+//! .#..
+//! ....
+//! settings 2
+//! max_fruit = 5
+//! spawn_on_growth = true
+//! inputs 8
+//! --RRDDL-
+//! ```
+//!
+//! The `level` line gives the number of following lines holding the level, in the format
+//! described in the `level` module. The `settings` line gives the number of following lines
+//! holding the gameplay rules as TOML, rules which are not listed keep their default values.
+//! Every input is a single character: `U`, `D`, `L`, `R` for a direction and `-` for no move.
+//! Inputs may be split over many lines.
+//!
+//! Older versions are still read: version 1 stores `board <width> <height>` and
+//! `start <x> <y>` lines instead of the level, version 2 adds a `topology` line after them.
+//! Versions before 4 have no `settings` and are played with the default rules. Versions before 5
+//! have no speed settings and are played at a constant speed, which is also the default.
+
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::game_logic::{GameLogic, GameSettings};
+use crate::level::{self, Level, LevelError};
+use crate::{Direction, Segment};
+
+/// Version of the replay file format written by this build.
+pub const REPLAY_FORMAT_VERSION: u32 = 5;
+
+const REPLAY_MAGIC: &str = "snake2d-replay";
+const INPUTS_PER_LINE: usize = 80;
+
+/// Error returned when a replay cannot be read or written.
+#[derive(Debug)]
+pub enum ReplayError {
+    /// Reading or writing the replay file has failed.
+    Io(io::Error),
+    /// Replay file was written in a format version this build does not understand.
+    UnsupportedVersion(u32),
+    /// Replay file is malformed.
+    Format {
+        /// Line (counted from 1) where the problem was found, 0 if the problem concerns the
+        /// replay as a whole.
+        line: usize,
+        /// Description of the problem.
+        message: String,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Io(error) => write!(f, "replay I/O error: {}", error),
+            ReplayError::UnsupportedVersion(version) =>
+                write!(f, "unsupported replay format version {} (expected {})", version, REPLAY_FORMAT_VERSION),
+            ReplayError::Format { line, message } => write!(f, "malformed replay at line {}: {}", line, message),
+        }
+    }
+}
+
+impl Error for ReplayError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ReplayError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ReplayError {
+    fn from(error: io::Error) -> Self {
+        ReplayError::Io(error)
+    }
+}
+
+/// Recorded game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    /// Seed used to generate obstacles.
+    pub seed: u64,
+    /// Level the game started in.
+    pub level: Level,
+    /// Gameplay rules the game was played with.
+    pub settings: GameSettings,
+    /// Move passed to the main loop on every tick.
+    pub inputs: Vec<Option<Direction>>,
+}
+
+impl Replay {
+    /// Creates a new game in the same state as the recorded one before its first tick.
+    pub fn new_game(&self) -> GameLogic {
+        GameLogic::from_level(&self.level, self.seed).with_settings(self.settings.clone())
+    }
+
+    /// Loads a replay from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Replay, ReplayError> {
+        Replay::read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Saves the replay to a file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ReplayError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the replay in the text format described in the module documentation.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), ReplayError> {
+        writeln!(writer, "{} {}", REPLAY_MAGIC, REPLAY_FORMAT_VERSION)?;
+        writeln!(writer, "seed {}", self.seed)?;
+        let level = self.level.to_string();
+        writeln!(writer, "level {}", level.lines().count())?;
+        write!(writer, "{}", level)?;
+        let settings = toml::to_string(&self.settings).expect("Game settings are always serializable!");
+        writeln!(writer, "settings {}", settings.lines().count())?;
+        write!(writer, "{}", settings)?;
+        writeln!(writer, "inputs {}", self.inputs.len())?;
+        for chunk in self.inputs.chunks(INPUTS_PER_LINE) {
+            let line: String = chunk.iter().map(|&input| input_to_char(input)).collect();
+            writeln!(writer, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a replay in the text format described in the module documentation.
+    pub fn read_from<R: BufRead>(reader: R) -> Result<Replay, ReplayError> {
+        let mut lines = reader.lines().enumerate().map(|(index, line)| (index + 1, line));
+        let mut next_line = |expected: &str| -> Result<(usize, String), ReplayError> {
+            match lines.next() {
+                Some((number, line)) => Ok((number, line?)),
+                None => Err(ReplayError::Format {
+                    line: 0,
+                    message: format!("unexpected end of file, expected {}", expected),
+                }),
+            }
+        };
+
+        let (number, line) = next_line("replay header")?;
+        let version = parse_header(number, &line)?;
+        if version == 0 || version > REPLAY_FORMAT_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+
+        let (number, line) = next_line("seed")?;
+        let seed = parse_field::<u64>(number, &line, "seed", 1)?[0];
+        let level = if version >= 3 {
+            let (number, line) = next_line("level")?;
+            let level_lines = parse_field::<usize>(number, &line, "level", 1)?[0];
+            let mut text = String::new();
+            for _ in 0..level_lines {
+                text.push_str(&next_line("level")?.1);
+                text.push('\n');
+            }
+            Level::parse(&text).map_err(|error| level_error(number, error))?
+        } else {
+            read_old_level(version, &mut next_line)?
+        };
+        let settings = if version >= 4 {
+            let (number, line) = next_line("settings")?;
+            let settings_lines = parse_field::<usize>(number, &line, "settings", 1)?[0];
+            let mut text = String::new();
+            for _ in 0..settings_lines {
+                text.push_str(&next_line("settings")?.1);
+                text.push('\n');
+            }
+            let settings: GameSettings = toml::from_str(&text)
+                .map_err(|error| format_error(number, &format!("invalid settings: {}", error.message())))?;
+            settings.validate().map_err(|message| format_error(number, &format!("invalid settings: {}", message)))?;
+            settings
+        } else {
+            GameSettings::default()
+        };
+        let (number, line) = next_line("inputs count")?;
+        let inputs_count = parse_field::<usize>(number, &line, "inputs", 1)?[0];
+
+        // the count is not trusted to preallocate, the inputs which follow must match it
+        let mut inputs = Vec::new();
+        while inputs.len() < inputs_count {
+            let (number, line) = next_line("inputs")?;
+            for character in line.trim_end().chars() {
+                match char_to_input(character) {
+                    Some(input) => inputs.push(input),
+                    None => return Err(format_error(number, &format!("unknown input '{}'", character))),
+                }
+            }
+        }
+        if inputs.len() != inputs_count {
+            return Err(format_error(0, &format!("expected {} inputs, found {}", inputs_count, inputs.len())));
+        }
+        for (number, line) in lines {
+            if !line?.trim().is_empty() {
+                return Err(format_error(number, &format!("expected {} inputs, found more", inputs_count)));
+            }
+        }
+
+        Ok(Replay {
+            seed,
+            level,
+            settings,
+            inputs,
+        })
+    }
+}
+
+fn format_error(line: usize, message: &str) -> ReplayError {
+    ReplayError::Format { line, message: String::from(message) }
+}
+
+// converts an error of a level embedded after the given line
+fn level_error(level_line: usize, error: LevelError) -> ReplayError {
+    match error {
+        LevelError::Format { line: 0, message } => ReplayError::Format { line: level_line, message },
+        LevelError::Format { line, message } => ReplayError::Format { line: level_line + line, message },
+        error => format_error(level_line, &error.to_string()),
+    }
+}
+
+// reads board size, starting segment and topology stored by versions 1 and 2
+fn read_old_level<F>(version: u32, next_line: &mut F) -> Result<Level, ReplayError>
+    where F: FnMut(&str) -> Result<(usize, String), ReplayError> {
+    let (number, line) = next_line("board size")?;
+    let board = parse_field::<usize>(number, &line, "board", 2)?;
+    if board[0] == 0 || board[1] == 0 {
+        return Err(format_error(number, "board size must be positive"));
+    }
+    let (number, line) = next_line("starting segment")?;
+    let start = parse_field::<i32>(number, &line, "start", 2)?;
+    if start[0] < 0 || start[1] < 0 || start[0] as usize >= board[0] || start[1] as usize >= board[1] {
+        return Err(format_error(number, "starting segment is outside of the board"));
+    }
+    let mut level = Level::empty(board[0], board[1], Segment::new(start[0], start[1]));
+    if version >= 2 {
+        let (number, line) = next_line("topology")?;
+        let name = parse_field::<String>(number, &line, "topology", 1)?.remove(0);
+        level.topology = level::str_to_topology(&name)
+            .ok_or_else(|| format_error(number, &format!("unknown topology '{}'", name)))?;
+    }
+    Ok(level)
+}
+
+fn parse_header(number: usize, line: &str) -> Result<u32, ReplayError> {
+    let mut words = line.split_whitespace();
+    if words.next() != Some(REPLAY_MAGIC) {
+        return Err(format_error(number, "not a snake2d replay file"));
+    }
+    match words.next().map(str::parse::<u32>) {
+        Some(Ok(version)) => Ok(version),
+        _ => Err(format_error(number, "missing replay format version")),
+    }
+}
+
+fn parse_field<T: std::str::FromStr>(number: usize, line: &str, name: &str, count: usize) -> Result<Vec<T>, ReplayError> {
+    let mut words = line.split_whitespace();
+    if words.next() != Some(name) {
+        return Err(format_error(number, &format!("expected '{}' field", name)));
+    }
+    let values = words.map(str::parse::<T>).collect::<Result<Vec<T>, _>>()
+        .map_err(|_| format_error(number, &format!("invalid value of '{}' field", name)))?;
+    if values.len() != count {
+        return Err(format_error(number, &format!("'{}' field expects {} value(s)", name, count)));
+    }
+    Ok(values)
+}
+
+fn input_to_char(input: Option<Direction>) -> char {
+    match input {
+        Some(Direction::Up) => 'U',
+        Some(Direction::Down) => 'D',
+        Some(Direction::Left) => 'L',
+        Some(Direction::Right) => 'R',
+        None => '-',
+    }
+}
+
+fn char_to_input(character: char) -> Option<Option<Direction>> {
+    match character {
+        'U' => Some(Some(Direction::Up)),
+        'D' => Some(Some(Direction::Down)),
+        'L' => Some(Some(Direction::Left)),
+        'R' => Some(Some(Direction::Right)),
+        '-' => Some(None),
+        _ => None,
+    }
+}
+
+/// Captures moves passed to the main loop of a game.
+pub struct Recorder {
+    replay: Replay,
+}
+
+impl Recorder {
+    /// Starts recording a game. Must be called before the first tick of the game.
+    pub fn new(game_logic: &GameLogic) -> Recorder {
+        Recorder {
+            replay: Replay {
+                seed: game_logic.get_seed(),
+                level: game_logic.to_level(),
+                settings: game_logic.get_settings().clone(),
+                inputs: Vec::new(),
+            },
+        }
+    }
+
+    /// Records a move passed to the main loop.
+    pub fn record(&mut self, input: Option<Direction>) {
+        self.replay.inputs.push(input);
+    }
+
+    /// Returns the game recorded so far.
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+}
+
+/// Feeds recorded moves back to a game.
+pub struct ReplayPlayer {
+    replay: Replay,
+    position: usize,
+}
+
+impl ReplayPlayer {
+    /// Creates a player positioned at the first recorded tick.
+    pub fn new(replay: Replay) -> ReplayPlayer {
+        ReplayPlayer {
+            replay,
+            position: 0,
+        }
+    }
+
+    /// Returns the replay being played.
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Returns the move for the next tick, or `None` once all recorded ticks were played.
+    pub fn next_input(&mut self) -> Option<Option<Direction>> {
+        let input = self.replay.inputs.get(self.position).copied();
+        if input.is_some() {
+            self.position += 1;
+        }
+        input
+    }
+
+    /// Returns true if all recorded ticks were played.
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.replay.inputs.len()
+    }
+
+    /// Plays all remaining ticks on the given game.
+    pub fn play_to_end(&mut self, game_logic: &mut GameLogic) {
+        while let Some(input) = self.next_input() {
+            game_logic.main_loop(input);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Topology;
+
+    fn get_replay() -> Replay {
+        let mut inputs = vec![None, Some(Direction::Right), Some(Direction::Down), Some(Direction::Left)];
+        inputs.extend(vec![Some(Direction::Up); 100]);
+        let mut level = Level::empty(5, 4, Segment::new(1, 2));
+        level.topology = Topology::Toroidal;
+        level.walls = vec![(0, 0), (4, 3)];
+        level.fruit = vec![(3, 1, 2)];
+        Replay {
+            seed: 1234,
+            level,
+            settings: GameSettings { max_fruit: 3, ..GameSettings::default() },
+            inputs,
+        }
+    }
+
+    fn replay_to_string(replay: &Replay) -> String {
+        let mut buffer = Vec::new();
+        replay.write_to(&mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn given_replay_when_written_and_read_then_replay_is_the_same() {
+        let replay = get_replay();
+        let text = replay_to_string(&replay);
+
+        assert_eq!(text.lines().take(10).collect::<Vec<&str>>(), vec!["snake2d-replay 5", "seed 1234", "level 8",
+            "snake2d-level 1", "snake 1,2", "topology toroidal", "map", "#....", "...2.", "....."]);
+        assert!(text.contains("\n....#\nsettings 18\nmax_fruit = 3\n"));
+        assert!(text.contains("\n[score]\nfruit_scores = [10, 20, 30]\n"));
+        assert!(text.contains("\nspeed_multiplier = false\n\n[speed]\nmetric = \"length\"\n"));
+        assert!(text.contains("\nmax_tick_rate = 30\ninputs 104\n-RDL"));
+        assert_eq!(Replay::read_from(text.as_bytes()).unwrap(), replay);
+    }
+
+    #[test]
+    fn given_unknown_format_version_when_reading_then_error_is_returned() {
+        let text = replay_to_string(&get_replay()).replacen("snake2d-replay 5", "snake2d-replay 99", 1);
+        match Replay::read_from(text.as_bytes()) {
+            Err(ReplayError::UnsupportedVersion(99)) => (),
+            other => panic!("Expected unsupported version error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn given_version_1_replay_when_reading_then_board_is_bounded() {
+        let text = "snake2d-replay 1\nseed 1\nboard 5 5\nstart 1 1\ninputs 3\nRD-\n";
+        let replay = Replay::read_from(text.as_bytes()).unwrap();
+
+        assert_eq!(replay.level, Level::empty(5, 5, Segment::new(1, 1)));
+        assert_eq!(replay.settings, GameSettings::default());
+        assert_eq!(replay.inputs, vec![Some(Direction::Right), Some(Direction::Down), None]);
+    }
+
+    #[test]
+    fn given_version_2_replay_when_reading_then_topology_is_read() {
+        let text = "snake2d-replay 2\nseed 1\nboard 5 5\nstart 1 1\ntopology toroidal\ninputs 0\n";
+        let replay = Replay::read_from(text.as_bytes()).unwrap();
+
+        assert_eq!(replay.level.topology, Topology::Toroidal);
+        assert_eq!((replay.level.width, replay.level.height), (5, 5));
+    }
+
+    #[test]
+    fn given_malformed_replay_when_reading_then_error_points_to_line() {
+        let text = "snake2d-replay 1\nseed 1\nboard 5 5\nstart 1 1\ninputs 3\nRX-\n";
+        match Replay::read_from(text.as_bytes()) {
+            Err(ReplayError::Format { line: 6, .. }) => (),
+            other => panic!("Expected format error at line 6, got {:?}", other),
+        }
+
+        let text = "snake2d-replay 1\nseed 1\nboard 5 5\nstart 5 1\ninputs 0\n";
+        match Replay::read_from(text.as_bytes()) {
+            Err(ReplayError::Format { line: 4, .. }) => (),
+            other => panic!("Expected format error at line 4, got {:?}", other),
+        }
+
+        let text = "snake2d-replay 3\nseed 1\nlevel 4\nsnake2d-level 1\nsnake 1,1\nmap\n.x.\ninputs 0\n";
+        match Replay::read_from(text.as_bytes()) {
+            Err(ReplayError::Format { line: 7, .. }) => (),
+            other => panic!("Expected format error at line 7, got {:?}", other),
+        }
+
+        let text = "snake2d-replay 4\nseed 1\nlevel 4\nsnake2d-level 1\nsnake 1,0\nmap\n...\nsettings 1\nmax_fruit = -1\ninputs 0\n";
+        match Replay::read_from(text.as_bytes()) {
+            Err(ReplayError::Format { line: 8, .. }) => (),
+            other => panic!("Expected format error at line 8, got {:?}", other),
+        }
+
+        let text = "snake2d-replay 1\nseed 1\nboard 5 5\nstart 1 1\ninputs 4\nRR\n";
+        assert!(Replay::read_from(text.as_bytes()).is_err());
+        assert!(Replay::read_from("some other file".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn given_inputs_count_not_matching_inputs_when_reading_then_error_is_returned() {
+        let text = "snake2d-replay 1\nseed 1\nboard 5 5\nstart 1 1\ninputs 18446744073709551615\nRR\n";
+        match Replay::read_from(text.as_bytes()) {
+            Err(ReplayError::Format { .. }) => (),
+            other => panic!("Expected format error, got {:?}", other),
+        }
+
+        let text = "snake2d-replay 1\nseed 1\nboard 5 5\nstart 1 1\ninputs 2\nRR\nDD\n";
+        match Replay::read_from(text.as_bytes()) {
+            Err(ReplayError::Format { line: 7, .. }) => (),
+            other => panic!("Expected format error at line 7, got {:?}", other),
+        }
+        let text = "snake2d-replay 1\nseed 1\nboard 5 5\nstart 1 1\ninputs 2\nRR\n\n";
+        assert_eq!(Replay::read_from(text.as_bytes()).unwrap().inputs.len(), 2);
+    }
+
+    #[test]
+    fn given_recorded_game_when_replayed_then_game_is_reproduced() {
+        let mut game_logic = GameLogic::with_seed(15, 15, Segment::new(7, 7), 77)
+            .with_topology(Topology::Toroidal)
+            .with_walls(&[(3, 3), (3, 4), (10, 10)])
+            .with_settings(GameSettings { max_spawned_fruit: 4, max_fruit_points: 1, ..GameSettings::default() });
+        let mut recorder = Recorder::new(&game_logic);
+        let moves = [None, Some(Direction::Up), None, Some(Direction::Left), Some(Direction::Down), None, None,
+            Some(Direction::Right), None, Some(Direction::Up)];
+        for &input in moves.iter() {
+            recorder.record(input);
+            game_logic.main_loop(input);
+        }
+
+        let replay = Replay::read_from(replay_to_string(recorder.replay()).as_bytes()).unwrap();
+        let mut player = ReplayPlayer::new(replay);
+        let mut replayed_game = player.replay().new_game();
+        player.play_to_end(&mut replayed_game);
+
+        assert!(player.is_finished());
+        assert_eq!(player.next_input(), None);
+        let mut expected_obstacles = game_logic.get_obstacles();
+        let mut actual_obstacles = replayed_game.get_obstacles();
+        expected_obstacles.sort();
+        actual_obstacles.sort();
+        assert_eq!(actual_obstacles, expected_obstacles);
+        assert_eq!(replayed_game.get_snake_segments(), game_logic.get_snake_segments());
+        assert_eq!(replayed_game.get_status(), game_logic.get_status());
+    }
+}
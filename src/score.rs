@@ -0,0 +1,149 @@
+//! Scoring of eaten fruit.
+//!
+//! The score is counted separately from the snake length. Every fruit kind, identified by the
+//! points it grows the snake by, has its own score value. The value can be multiplied by a combo,
+//! counting fruit eaten shortly one after another, and by the speed of the game.
+
+use serde::{Deserialize, Serialize};
+
+/// Tick rate at which the speed multiplier is 1.
+pub const REFERENCE_TICK_RATE: u64 = 15;
+
+/// Rules of scoring, part of the gameplay rules.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScoreSettings {
+    /// Score of a fruit growing the snake by 1, 2, 3... points. Fruit beyond the list score as
+    /// many points as they grow the snake by.
+    pub fruit_scores: Vec<usize>,
+    /// A fruit eaten at most this many ticks after the previous one continues the combo, 0
+    /// disables combos.
+    pub combo_ticks: usize,
+    /// Highest combo multiplier.
+    pub max_combo: usize,
+    /// Multiply scores by the tick rate divided by `REFERENCE_TICK_RATE`.
+    pub speed_multiplier: bool,
+}
+
+impl Default for ScoreSettings {
+    fn default() -> Self {
+        ScoreSettings {
+            fruit_scores: vec![10, 20, 30],
+            combo_ticks: 0,
+            max_combo: 5,
+            speed_multiplier: false,
+        }
+    }
+}
+
+impl ScoreSettings {
+    /// Checks that the multipliers are positive.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_combo == 0 {
+            return Err(String::from("'max_combo' must be positive"));
+        }
+        Ok(())
+    }
+
+    /// Returns the score of a fruit before multipliers are applied.
+    pub fn fruit_score(&self, points: u8) -> usize {
+        match (points as usize).checked_sub(1).and_then(|index| self.fruit_scores.get(index)) {
+            Some(&score) => score,
+            None => points as usize,
+        }
+    }
+}
+
+/// Score of a single game.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Score {
+    total: usize,
+    combo: usize,
+    last_fruit_tick: Option<usize>,
+}
+
+impl Score {
+    /// Returns the score collected so far.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Returns the current combo multiplier, 1 if no combo is running.
+    pub fn combo(&self) -> usize {
+        self.combo.max(1)
+    }
+
+    /// Adds the score of a fruit eaten on the given tick, returns the added score.
+    pub fn add_fruit(&mut self, settings: &ScoreSettings, points: u8, tick: usize, tick_rate: u64) -> usize {
+        let continues_combo = self.last_fruit_tick
+            .is_some_and(|last_tick| tick - last_tick <= settings.combo_ticks);
+        self.combo = if continues_combo { (self.combo + 1).min(settings.max_combo) } else { 1 };
+        self.last_fruit_tick = Some(tick);
+
+        let mut score = (settings.fruit_score(points) * self.combo) as f64;
+        if settings.speed_multiplier {
+            score *= tick_rate as f64 / REFERENCE_TICK_RATE as f64;
+        }
+        let score = score.round() as usize;
+        self.total += score;
+        score
+    }
+
+    /// Ends a running combo if the combo window has passed.
+    pub fn tick(&mut self, settings: &ScoreSettings, tick: usize) {
+        if self.last_fruit_tick.is_some_and(|last_tick| tick - last_tick > settings.combo_ticks) {
+            self.combo = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_fruit_kinds_then_scores_are_looked_up() {
+        let settings = ScoreSettings::default();
+        assert_eq!(settings.fruit_score(1), 10);
+        assert_eq!(settings.fruit_score(3), 30);
+        assert_eq!(settings.fruit_score(7), 7);
+        assert_eq!(settings.fruit_score(0), 0);
+    }
+
+    #[test]
+    fn given_combos_disabled_when_fruit_are_eaten_then_scores_are_added() {
+        let settings = ScoreSettings::default();
+        let mut score = Score::default();
+
+        assert_eq!(score.add_fruit(&settings, 1, 3, 15), 10);
+        assert_eq!(score.add_fruit(&settings, 2, 4, 15), 20);
+        assert_eq!(score.total(), 30);
+        assert_eq!(score.combo(), 1);
+    }
+
+    #[test]
+    fn given_combos_when_fruit_are_eaten_quickly_then_multiplier_grows_up_to_max() {
+        let settings = ScoreSettings { combo_ticks: 5, max_combo: 3, ..ScoreSettings::default() };
+        let mut score = Score::default();
+
+        assert_eq!(score.add_fruit(&settings, 1, 10, 15), 10);
+        assert_eq!(score.add_fruit(&settings, 1, 15, 15), 20);
+        assert_eq!(score.add_fruit(&settings, 1, 16, 15), 30);
+        assert_eq!(score.add_fruit(&settings, 1, 17, 15), 30);
+        score.tick(&settings, 22);
+        assert_eq!(score.combo(), 3);
+        score.tick(&settings, 23);
+        assert_eq!(score.combo(), 1);
+        assert_eq!(score.add_fruit(&settings, 1, 23, 15), 10);
+        assert_eq!(score.total(), 100);
+    }
+
+    #[test]
+    fn given_speed_multiplier_when_fruit_is_eaten_then_score_scales_with_tick_rate() {
+        let settings = ScoreSettings { speed_multiplier: true, ..ScoreSettings::default() };
+        let mut score = Score::default();
+
+        assert_eq!(score.add_fruit(&settings, 1, 1, 30), 20);
+        assert_eq!(score.add_fruit(&settings, 3, 100, 10), 20);
+    }
+}
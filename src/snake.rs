@@ -1,5 +1,6 @@
 use std::collections::{LinkedList, HashSet};
 use crate::Segment;
+#[cfg(test)]
 use crate::Direction;
 
 pub type Body = LinkedList<Segment>;
@@ -14,6 +15,7 @@ pub struct Snake {
 }
 
 impl Snake {
+    #[allow(clippy::ptr_arg)]
     pub fn new(segments: &Vec<Segment>) -> Snake {
         let mut snake_segments = LinkedList::new();
         snake_segments.extend(segments);
@@ -23,9 +25,17 @@ impl Snake {
         }
     }
 
+    #[cfg(test)]
     pub fn move_body(&mut self, direction: &Direction) {
         assert!(!self.body.is_empty());
-        self.move_body_internal(direction);
+        let &snake_head = self.body.front().unwrap();
+        self.move_body_to(direction.nearest_segment(&snake_head));
+    }
+
+    /// Moves the head to the given segment, which must be next to the current head.
+    pub fn move_body_to(&mut self, new_segment: Segment) {
+        assert!(!self.body.is_empty());
+        self.move_body_internal(new_segment);
 		
 	    if self.segments_to_grow_by > 0 {
             self.segments_to_grow_by -= 1;
@@ -47,13 +57,12 @@ impl Snake {
         self.body.iter().all(|x| uniq.insert(x))
     }
 
+    #[allow(clippy::needless_return)]
     pub fn len(&self) -> usize {
         return self.body.len() + self.segments_to_grow_by;
     }
 
-    fn move_body_internal(&mut self, direction: &Direction) {
-        let &snake_head = self.body.front().unwrap();
-        let new_segment = direction.nearest_segment(&snake_head);
+    fn move_body_internal(&mut self, new_segment: Segment) {
         if self.body.len() > 1  {
             assert!(self.body.iter().nth(1).unwrap() != &new_segment);
         }
@@ -199,12 +208,14 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn given_snake_with_no_duplicates_then_has_unique_segments_returns_false() {
         let snake = Snake::new(&get_4_segment_snake());
         assert_eq!(snake.has_unique_segments(), true);
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn given_snake_with_duplicates_then_has_unique_segments_returns_true() {
         let snake = Snake::new(&vec![
             Segment::new(2, 2),
@@ -216,6 +227,7 @@ mod tests {
         assert_eq!(snake.has_unique_segments(), false);
     }
 
+    #[allow(clippy::ptr_arg, non_fmt_panics)]
     fn test_move_generic(segment: Segment, direction: Direction, body: &Vec<Segment>, segments_to_grow:usize) {
         let mut snake = Snake::new(body);
         assert_eq!(snake.segments_to_grow_by, 0);
@@ -0,0 +1,262 @@
+//! Software game view.
+//!
+//! Draws the same scene as the OpenGL `GameView`, but on the CPU into an RGBA image. It does not
+//! need a GL context, so rendered frames can be compared against reference images in tests.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use glob::glob;
+use graphics::types::Color;
+use image::{imageops, Rgba, RgbaImage};
+
+use crate::game_view::GameViewSettings;
+use crate::renderer::{Frame, Renderer};
+use crate::Obstacle;
+
+/// Renders frames into RGBA images of a fixed size.
+pub struct SoftwareView {
+    /// Stores game view settings.
+    pub settings: GameViewSettings,
+    /// lookup for textures
+    pub textures: HashMap<String, RgbaImage>,
+    /// Width and height of rendered images in pixels
+    image_size: (u32, u32),
+}
+
+impl SoftwareView {
+    /// Creates a new view rendering images of `width` x `height` pixels.
+    pub fn new(settings: GameViewSettings, width: u32, height: u32) -> SoftwareView {
+        assert!(width > 0 && height > 0);
+        SoftwareView {
+            settings,
+            textures: Default::default(),
+            image_size: (width, height),
+        }
+    }
+
+    /// loads textures from asset folder
+    pub fn load_textures(&mut self, asset_folder: &str) {
+        let assets = find_folder::Search::ParentsThenKids(3, 3).for_folder(asset_folder).unwrap();
+        self.load_textures_from_path(&assets);
+    }
+
+    /// loads all PNG textures from the given directory
+    pub fn load_textures_from_path(&mut self, directory: &Path) {
+        for path in glob(directory.join("*.png").to_str().unwrap()).expect("Failed to find textures in asset directory!").flatten() {
+            let path_stem = path.file_stem().unwrap().to_str().unwrap();
+            let texture = image::open(&path).expect("Failed to load texture!").to_rgba();
+            self.textures.insert(String::from(path_stem), texture);
+        }
+    }
+
+    /// Draws a snapshot of a game into a new image
+    pub fn draw_frame(&self, frame: &Frame) -> RgbaImage {
+        let (width, height) = self.image_size;
+        let mut image = RgbaImage::from_pixel(width, height, to_rgba(self.settings.background_color));
+        let (board_width, board_height) = frame.board_size;
+        let segment_width = width as f64 / board_width as f64;
+        let segment_height = height as f64 / board_height as f64;
+        let field_rectangle = |x: f64, y: f64| {
+            let left = (x * segment_width).round() as i64;
+            let top = (y * segment_height).round() as i64;
+            let right = ((x + 1.0) * segment_width).round() as i64;
+            let bottom = ((y + 1.0) * segment_height).round() as i64;
+            (left, top, right - left, bottom - top)
+        };
+
+        for (index, &(x, y)) in frame.interpolated_snake().iter().enumerate().rev() {
+            let color = if index == 0 {
+                self.settings.snake_head_color
+            } else {
+                self.settings.snake_body_color
+            };
+            fill_rectangle(&mut image, field_rectangle(x, y), color);
+        }
+
+        for &(x, y, obstacle) in frame.obstacles.iter() {
+            match obstacle {
+                Obstacle::Apple(points) => {
+                    let texture = self.settings.fruit_name(points)
+                        .and_then(|texture_name| self.textures.get(texture_name));
+                    let (left, top, field_width, field_height) = field_rectangle(x as f64, y as f64);
+                    match texture {
+                        Some(texture) => {
+                            let scaled = imageops::resize(texture, field_width as u32, field_height as u32,
+                                                          imageops::FilterType::Nearest);
+                            imageops::overlay(&mut image, &scaled, left as u32, top as u32);
+                        },
+                        None => fill_rectangle(&mut image, (left, top, field_width, field_height),
+                                               self.settings.fruit_color),
+                    }
+                },
+                Obstacle::Wall => fill_rectangle(&mut image, field_rectangle(x as f64, y as f64), self.settings.wall_color),
+                Obstacle::None => (),
+            }
+        }
+
+        if frame.status.is_over() {
+            let color = if frame.status.is_won() { self.settings.game_won_color } else { self.settings.game_over_color };
+            fill_rectangle(&mut image, (0, 0, width as i64, height as i64), color);
+        }
+        image
+    }
+}
+
+impl Renderer for SoftwareView {
+    type Output = RgbaImage;
+
+    fn render(&mut self, frame: &Frame) -> RgbaImage {
+        self.draw_frame(frame)
+    }
+}
+
+fn to_rgba(color: Color) -> Rgba<u8> {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Rgba([channel(color[0]), channel(color[1]), channel(color[2]), channel(color[3])])
+}
+
+// blends the color over the given rectangle, clipping it to the image
+fn fill_rectangle(image: &mut RgbaImage, (left, top, width, height): (i64, i64, i64, i64), color: Color) {
+    let alpha = color[3].clamp(0.0, 1.0);
+    let source = to_rgba(color);
+    let x_range = left.max(0)..(left + width).min(image.width() as i64);
+    let y_range = top.max(0)..(top + height).min(image.height() as i64);
+    for y in y_range {
+        for x in x_range.clone() {
+            let pixel = image.get_pixel_mut(x as u32, y as u32);
+            for channel in 0..3 {
+                let blended = source[channel] as f32 * alpha + pixel[channel] as f32 * (1.0 - alpha);
+                pixel[channel] = blended.round() as u8;
+            }
+            pixel[3] = (255.0 * alpha + pixel[3] as f32 * (1.0 - alpha)).round() as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_logic::{GameOverCause, GameStatus, WinCause};
+    use crate::{Segment, Topology};
+    use std::path::PathBuf;
+
+    fn get_view() -> SoftwareView {
+        let mut view = SoftwareView::new(GameViewSettings::new(), 100, 100);
+        view.load_textures_from_path(&Path::new(env!("CARGO_MANIFEST_DIR")).join("assets"));
+        view
+    }
+
+    // .....
+    // .@#..
+    // ..#A.
+    // B.##.
+    // ....C
+    fn get_frame() -> Frame {
+        Frame {
+            board_size: (5, 5),
+            topology: Topology::Bounded,
+            snake: vec![Segment::new(1, 1), Segment::new(2, 1), Segment::new(2, 2), Segment::new(2, 3),
+                        Segment::new(3, 3)],
+            previous_snake: Vec::new(),
+            progress: 1.0,
+            obstacles: vec![(0, 3, Obstacle::Apple(2)), (3, 2, Obstacle::Apple(1)), (4, 4, Obstacle::Apple(3))],
+            score: 50,
+            length: 5,
+            combo: 1,
+            speed_level: 1,
+            status: GameStatus::Running,
+            paused: false,
+            tick: 10,
+        }
+    }
+
+    fn golden_path(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden").join(format!("{}.png", name))
+    }
+
+    // compares the image against a reference one, set UPDATE_GOLDEN=1 to overwrite references
+    fn assert_matches_golden(image: &RgbaImage, name: &str) {
+        let path = golden_path(name);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            image.save(&path).unwrap();
+        }
+        let expected = image::open(&path).expect("Missing golden image!").to_rgba();
+        assert_eq!(image.dimensions(), expected.dimensions());
+        if let Some((x, y, pixel)) = image.enumerate_pixels().find(|&(x, y, pixel)| pixel != expected.get_pixel(x, y)) {
+            panic!("Pixel ({}, {}) of {} differs from golden image: {:?} != {:?}", x, y, name, pixel,
+                   expected.get_pixel(x, y));
+        }
+    }
+
+    #[test]
+    fn given_frame_when_rendered_then_image_uses_view_colors() {
+        let image = get_view().render(&get_frame());
+
+        assert_eq!(image.dimensions(), (100, 100));
+        assert_eq!(image.get_pixel(0, 0), &Rgba([255, 255, 255, 255]));
+        assert_eq!(image.get_pixel(30, 30), &Rgba([230, 0, 0, 255]));
+        assert_eq!(image.get_pixel(50, 30), &Rgba([0, 0, 179, 255]));
+        assert_eq!(image.get_pixel(70, 70), &Rgba([0, 0, 179, 255]));
+    }
+
+    #[test]
+    fn given_frame_when_rendered_then_image_matches_golden_image() {
+        assert_matches_golden(&get_view().render(&get_frame()), "running");
+    }
+
+    #[test]
+    fn given_frame_with_walls_when_rendered_then_image_matches_golden_image() {
+        let mut frame = get_frame();
+        frame.obstacles.extend(vec![(0, 0, Obstacle::Wall), (1, 0, Obstacle::Wall), (4, 1, Obstacle::Wall)]);
+        let image = get_view().render(&frame);
+
+        assert_eq!(image.get_pixel(10, 10), &Rgba([77, 77, 77, 255]));
+        assert_matches_golden(&image, "walls");
+    }
+
+    #[test]
+    fn given_game_over_frame_when_rendered_then_image_matches_golden_image() {
+        let mut frame = get_frame();
+        frame.snake.insert(0, Segment::new(1, 0));
+        frame.snake.insert(0, Segment::new(1, -1));
+        frame.status = GameStatus::GameOver { cause: GameOverCause::Wall, score: 7 };
+        let image = get_view().render(&frame);
+
+        assert_eq!(image.get_pixel(0, 0), &Rgba([128, 128, 128, 255]));
+        assert_matches_golden(&image, "game_over");
+    }
+
+    #[test]
+    fn given_won_frame_when_rendered_then_board_is_covered_with_won_color() {
+        let mut frame = get_frame();
+        frame.status = GameStatus::Won { cause: WinCause::TargetScore, score: 7 };
+        let image = get_view().render(&frame);
+
+        assert_eq!(image.get_pixel(0, 0), &Rgba([153, 214, 153, 255]));
+    }
+
+    #[test]
+    fn given_tick_in_progress_when_rendered_then_snake_is_drawn_between_fields() {
+        let mut frame = get_frame();
+        frame.previous_snake = vec![Segment::new(0, 1), Segment::new(1, 1), Segment::new(2, 1), Segment::new(2, 2),
+                                    Segment::new(2, 3)];
+        frame.progress = 0.5;
+        let image = get_view().render(&frame);
+
+        // the head is half way between (0,1) and (1,1), covering the body moving behind it
+        assert_eq!(image.get_pixel(12, 30), &Rgba([230, 0, 0, 255]));
+        assert_eq!(image.get_pixel(28, 30), &Rgba([230, 0, 0, 255]));
+        assert_eq!(image.get_pixel(8, 30), &Rgba([255, 255, 255, 255]));
+        assert_eq!(image.get_pixel(32, 30), &Rgba([0, 0, 179, 255]));
+    }
+
+    #[test]
+    fn given_fruit_without_texture_when_rendered_then_fruit_is_drawn_with_fruit_color() {
+        let mut frame = get_frame();
+        frame.obstacles.push((4, 0, Obstacle::Apple(7)));
+        let image = get_view().render(&frame);
+
+        assert_eq!(image.get_pixel(90, 10), &Rgba([0, 153, 0, 255]));
+    }
+}
@@ -0,0 +1,99 @@
+//! Speed of the game increasing as the snake grows.
+//!
+//! The game starts at speed level 1 and the base tick rate. Each threshold of the snake length
+//! or score which is reached starts the next level, speeding the game up by a fixed number of
+//! ticks per second until the cap is reached. There are no thresholds by default, so the game
+//! keeps its classic constant pace unless a curve is configured.
+
+use serde::{Deserialize, Serialize};
+
+/// Quantity compared against the thresholds of speed levels.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpeedMetric {
+    /// Length of the snake, including pending growth.
+    Length,
+    /// Score collected so far.
+    Score,
+}
+
+/// Difficulty curve, part of the gameplay rules.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpeedSettings {
+    /// Quantity the thresholds apply to.
+    pub metric: SpeedMetric,
+    /// Ascending values of the metric starting levels 2, 3, 4... An empty list keeps the base
+    /// tick rate for the whole game.
+    pub thresholds: Vec<usize>,
+    /// Ticks per second added on each level.
+    pub step: u64,
+    /// Highest tick rate reached by speeding up. A base tick rate above it is kept as it is.
+    pub max_tick_rate: u64,
+}
+
+impl Default for SpeedSettings {
+    fn default() -> Self {
+        SpeedSettings {
+            metric: SpeedMetric::Length,
+            thresholds: Vec::new(),
+            step: 2,
+            max_tick_rate: 30,
+        }
+    }
+}
+
+impl SpeedSettings {
+    /// Checks that the thresholds are strictly ascending and the cap is a valid tick rate.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.thresholds.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(String::from("speed 'thresholds' must be strictly ascending"));
+        }
+        if self.max_tick_rate == 0 || self.max_tick_rate > 1000 {
+            return Err(String::from("'max_tick_rate' must be between 1 and 1000"));
+        }
+        Ok(())
+    }
+
+    /// Returns the speed level, starting from 1, reached with the given value of the metric.
+    pub fn level(&self, value: usize) -> usize {
+        1 + self.thresholds.iter().take_while(|&&threshold| value >= threshold).count()
+    }
+
+    /// Returns the tick rate of a speed level, starting from the base tick rate at level 1.
+    pub fn tick_rate(&self, base_tick_rate: u64, level: usize) -> u64 {
+        let faster = base_tick_rate.saturating_add(self.step.saturating_mul(level.saturating_sub(1) as u64));
+        faster.min(self.max_tick_rate.max(base_tick_rate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_thresholds_when_value_grows_then_level_increases() {
+        let settings = SpeedSettings { thresholds: vec![3, 6], ..SpeedSettings::default() };
+        assert_eq!(settings.level(0), 1);
+        assert_eq!(settings.level(2), 1);
+        assert_eq!(settings.level(3), 2);
+        assert_eq!(settings.level(100), 3);
+        assert_eq!(SpeedSettings { thresholds: vec![], ..SpeedSettings::default() }.level(100), 1);
+    }
+
+    #[test]
+    fn given_levels_when_tick_rate_is_computed_then_it_is_capped() {
+        let settings = SpeedSettings { step: 5, max_tick_rate: 22, ..SpeedSettings::default() };
+        assert_eq!(settings.tick_rate(10, 1), 10);
+        assert_eq!(settings.tick_rate(10, 2), 15);
+        assert_eq!(settings.tick_rate(10, 4), 22);
+        assert_eq!(settings.tick_rate(40, 3), 40);
+    }
+
+    #[test]
+    fn given_invalid_settings_when_validated_then_error_is_returned() {
+        assert!(SpeedSettings::default().validate().is_ok());
+        assert!(SpeedSettings { thresholds: vec![5, 5], ..SpeedSettings::default() }.validate().is_err());
+        assert!(SpeedSettings { max_tick_rate: 0, ..SpeedSettings::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,104 @@
+//! Fixed-timestep simulation driven by the elapsed time of update events.
+
+/// Longest time in seconds taken into account from a single update, so the game does not race
+/// through many ticks after the window was stalled e.g. while being dragged.
+pub const MAX_UPDATE_TIME: f64 = 0.25;
+
+/// Converts elapsed time into game ticks of a fixed length.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FixedTimestep {
+    /// Length of a tick in seconds.
+    tick_time: f64,
+    /// Time elapsed since the last tick, in seconds.
+    accumulator: f64,
+}
+
+impl FixedTimestep {
+    /// Creates a timestep running `tick_rate` ticks per second.
+    pub fn new(tick_rate: u64) -> FixedTimestep {
+        assert!(tick_rate > 0, "Tick rate must be positive!");
+        FixedTimestep { tick_time: 1.0 / tick_rate as f64, accumulator: 0.0 }
+    }
+
+    /// Returns the length of a tick in seconds.
+    pub fn tick_time(&self) -> f64 {
+        self.tick_time
+    }
+
+    /// Changes the number of ticks per second, keeping the time elapsed since the last tick.
+    pub fn set_tick_rate(&mut self, tick_rate: u64) {
+        assert!(tick_rate > 0, "Tick rate must be positive!");
+        self.tick_time = 1.0 / tick_rate as f64;
+    }
+
+    /// Adds elapsed time in seconds, to be consumed by `take_tick`.
+    pub fn add_time(&mut self, elapsed: f64) {
+        self.accumulator += elapsed.clamp(0.0, MAX_UPDATE_TIME);
+    }
+
+    /// Returns true and consumes the time of a tick if a whole tick has elapsed.
+    pub fn take_tick(&mut self) -> bool {
+        if self.accumulator < self.tick_time {
+            return false;
+        }
+        self.accumulator -= self.tick_time;
+        true
+    }
+
+    /// Returns the fraction of the next tick which has already elapsed, between 0 and 1.
+    pub fn progress(&self) -> f64 {
+        (self.accumulator / self.tick_time).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // adds elapsed time and returns the number of whole ticks taken
+    fn advance(timestep: &mut FixedTimestep, elapsed: f64) -> usize {
+        timestep.add_time(elapsed);
+        let mut ticks = 0;
+        while timestep.take_tick() {
+            ticks += 1;
+        }
+        ticks
+    }
+
+    #[test]
+    fn given_short_updates_when_advanced_then_ticks_come_at_tick_rate() {
+        let mut timestep = FixedTimestep::new(8);
+        let ticks: usize = (0..64).map(|_| advance(&mut timestep, 1.0 / 64.0)).sum();
+        assert_eq!(ticks, 8);
+        assert_eq!(timestep.progress(), 0.0);
+
+        assert_eq!(advance(&mut timestep, 0.0625), 0);
+        assert_eq!(timestep.progress(), 0.5);
+    }
+
+    #[test]
+    fn given_long_update_when_advanced_then_several_ticks_are_simulated() {
+        let mut timestep = FixedTimestep::new(16);
+        assert_eq!(advance(&mut timestep, 0.21875), 3);
+        assert_eq!(timestep.progress(), 0.5);
+    }
+
+    #[test]
+    fn given_faster_tick_rate_when_ticks_are_taken_then_elapsed_time_is_kept() {
+        let mut timestep = FixedTimestep::new(8);
+        timestep.add_time(0.125 + 0.0625);
+        assert!(timestep.take_tick());
+        assert!(!timestep.take_tick());
+        timestep.set_tick_rate(16);
+        assert!(timestep.take_tick());
+        assert!(!timestep.take_tick());
+    }
+
+    #[test]
+    fn given_stalled_update_when_advanced_then_elapsed_time_is_capped() {
+        let mut timestep = FixedTimestep::new(8);
+        assert_eq!(advance(&mut timestep, 10.0), 2);
+        assert_eq!(advance(&mut timestep, -1.0), 0);
+        assert_eq!(timestep.progress(), 0.0);
+    }
+}
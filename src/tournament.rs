@@ -0,0 +1,293 @@
+//! Tournaments of bots.
+//!
+//! A tournament plays headless games of every bot on every board with the same seeds and
+//! summarizes them per bot and board: scores, survival ticks, causes of death and the win rate.
+//! Reports are printed as a table, JSON or CSV.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::bot::BotKind;
+use crate::game_logic::{GameOverCause, GameSettings, GameStatus};
+use crate::headless::{GameSummary, HeadlessRunner, DEFAULT_MAX_TICKS};
+use crate::level::Level;
+
+/// Format of a tournament report.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReportFormat {
+    /// Aligned columns for reading in a terminal.
+    Table,
+    /// Array of objects, one per bot and board.
+    Json,
+    /// Header line followed by one line per bot and board.
+    Csv,
+}
+
+impl ReportFormat {
+    /// All report formats.
+    pub const ALL: [ReportFormat; 3] = [ReportFormat::Table, ReportFormat::Json, ReportFormat::Csv];
+
+    /// Returns the name the format is chosen by.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReportFormat::Table => "table",
+            ReportFormat::Json => "json",
+            ReportFormat::Csv => "csv",
+        }
+    }
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        ReportFormat::ALL.iter().copied().find(|format| format.name() == name).ok_or_else(|| {
+            let names: Vec<&str> = ReportFormat::ALL.iter().map(|format| format.name()).collect();
+            format!("unknown report format '{}', expected one of: {}", name, names.join(", "))
+        })
+    }
+}
+
+/// Statistics of the games of one bot on one board.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BotStatistics {
+    /// Name of the bot.
+    pub bot: String,
+    /// Board width in fields.
+    pub width: usize,
+    /// Board height in fields.
+    pub height: usize,
+    /// Number of games played.
+    pub games: usize,
+    /// Mean score of the games.
+    pub mean_score: f64,
+    /// Median score of the games.
+    pub median_score: f64,
+    /// Highest score of the games.
+    pub max_score: usize,
+    /// Mean number of ticks the games lasted.
+    pub mean_ticks: f64,
+    /// Median number of ticks the games lasted.
+    pub median_ticks: f64,
+    /// Highest number of ticks a game lasted.
+    pub max_ticks: usize,
+    /// Games lost by hitting a wall.
+    pub wall_deaths: usize,
+    /// Games lost by the snake hitting itself.
+    pub snake_deaths: usize,
+    /// Games stopped at the ticks limit.
+    pub timeouts: usize,
+    /// Games won.
+    pub wins: usize,
+    /// Fraction of the games won, between 0 and 1.
+    pub win_rate: f64,
+}
+
+impl BotStatistics {
+    /// Summarizes games of a bot on a `width` x `height` board.
+    pub fn from_summaries(bot: &str, (width, height): (usize, usize), summaries: &[GameSummary]) -> BotStatistics {
+        let scores: Vec<usize> = summaries.iter().map(|summary| summary.score).collect();
+        let ticks: Vec<usize> = summaries.iter().map(|summary| summary.ticks).collect();
+        let count = |predicate: fn(&GameStatus) -> bool| {
+            summaries.iter().filter(|summary| predicate(&summary.status)).count()
+        };
+        let wins = count(GameStatus::is_won);
+        BotStatistics {
+            bot: bot.to_string(),
+            width,
+            height,
+            games: summaries.len(),
+            mean_score: mean(&scores),
+            median_score: median(&scores),
+            max_score: scores.iter().copied().max().unwrap_or(0),
+            mean_ticks: mean(&ticks),
+            median_ticks: median(&ticks),
+            max_ticks: ticks.iter().copied().max().unwrap_or(0),
+            wall_deaths: count(|status| matches!(status, GameStatus::GameOver { cause: GameOverCause::Wall, .. })),
+            snake_deaths: count(|status| matches!(status, GameStatus::GameOver { cause: GameOverCause::Snake, .. })),
+            timeouts: count(|status| *status == GameStatus::Running),
+            wins,
+            win_rate: if summaries.is_empty() { 0.0 } else { wins as f64 / summaries.len() as f64 },
+        }
+    }
+}
+
+fn mean(values: &[usize]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<usize>() as f64 / values.len() as f64
+}
+
+fn median(values: &[usize]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    match sorted.len() {
+        0 => 0.0,
+        length if length % 2 == 1 => sorted[length / 2] as f64,
+        length => (sorted[length / 2 - 1] + sorted[length / 2]) as f64 / 2.0,
+    }
+}
+
+/// Games of bots played with the same seeds on the same boards.
+pub struct Tournament {
+    bots: Vec<BotKind>,
+    levels: Vec<Level>,
+    seeds: Vec<u64>,
+    settings: GameSettings,
+    max_ticks: usize,
+}
+
+impl Tournament {
+    /// Creates a tournament in which every bot plays one game per seed in every level.
+    pub fn new(bots: Vec<BotKind>, levels: Vec<Level>, seeds: Vec<u64>) -> Tournament {
+        Tournament {
+            bots,
+            levels,
+            seeds,
+            settings: GameSettings::default(),
+            max_ticks: DEFAULT_MAX_TICKS,
+        }
+    }
+
+    /// Sets the gameplay rules.
+    pub fn settings(mut self, settings: GameSettings) -> Tournament {
+        self.settings = settings;
+        self
+    }
+
+    /// Sets the number of ticks after which a game which has not ended is stopped.
+    pub fn max_ticks(mut self, max_ticks: usize) -> Tournament {
+        self.max_ticks = max_ticks;
+        self
+    }
+
+    /// Plays all games and returns statistics per bot and level, levels of each bot in the order
+    /// they were given.
+    pub fn run(&self) -> Vec<BotStatistics> {
+        let mut statistics = Vec::with_capacity(self.bots.len() * self.levels.len());
+        for &bot in self.bots.iter() {
+            for level in self.levels.iter() {
+                let runner = HeadlessRunner::with_level(level.clone())
+                    .settings(self.settings.clone())
+                    .max_ticks(self.max_ticks);
+                let summaries = runner.run_many(self.seeds.iter().copied(), || {
+                    let mut player = bot.create();
+                    move |game_logic| player.next_direction(game_logic)
+                });
+                statistics.push(BotStatistics::from_summaries(bot.name(), (level.width, level.height), &summaries));
+            }
+        }
+        statistics
+    }
+}
+
+/// Formats statistics of a tournament.
+pub fn report(statistics: &[BotStatistics], format: ReportFormat) -> String {
+    match format {
+        ReportFormat::Table => table_lines(statistics).iter().map(|line| format!("{}\n", line)).collect(),
+        ReportFormat::Json => {
+            let json = serde_json::to_string_pretty(statistics).expect("Statistics are always valid JSON!");
+            format!("{}\n", json)
+        },
+        ReportFormat::Csv => {
+            let mut csv = String::from("bot,width,height,games,mean_score,median_score,max_score,mean_ticks,\
+                median_ticks,max_ticks,wall_deaths,snake_deaths,timeouts,wins,win_rate\n");
+            for row in statistics {
+                csv.push_str(&format!("{},{},{},{},{:.2},{:.1},{},{:.2},{:.1},{},{},{},{},{},{:.4}\n", row.bot,
+                    row.width, row.height, row.games, row.mean_score, row.median_score, row.max_score,
+                    row.mean_ticks, row.median_ticks, row.max_ticks, row.wall_deaths, row.snake_deaths,
+                    row.timeouts, row.wins, row.win_rate));
+            }
+            csv
+        },
+    }
+}
+
+fn table_lines(statistics: &[BotStatistics]) -> Vec<String> {
+    let mut lines = vec![format!("{:<12} {:>7} {:>5} {:>9} {:>7} {:>6} {:>9} {:>7} {:>6} {:>5} {:>5} {:>7} {:>8}",
+                                 "Bot", "Board", "Games", "Score", "Median", "Max", "Ticks", "Median", "Max",
+                                 "Wall", "Self", "Timeout", "Win rate")];
+    for row in statistics {
+        let board = format!("{}x{}", row.width, row.height);
+        let wins = format!("{:.1}%", row.win_rate * 100.0);
+        lines.push(format!("{:<12} {:>7} {:>5} {:>9.1} {:>7.1} {:>6} {:>9.1} {:>7.1} {:>6} {:>5} {:>5} {:>7} {:>8}",
+                           row.bot, board, row.games, row.mean_score, row.median_score, row.max_score,
+                           row.mean_ticks, row.median_ticks, row.max_ticks, row.wall_deaths, row.snake_deaths,
+                           row.timeouts, wins));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_logic::WinCause;
+    use crate::Segment;
+
+    fn summary(status: GameStatus, score: usize, ticks: usize) -> GameSummary {
+        GameSummary { seed: 0, status, score, length: 1, ticks }
+    }
+
+    #[test]
+    fn given_summaries_when_summarized_then_statistics_count_causes_and_wins() {
+        let summaries = [
+            summary(GameStatus::GameOver { cause: GameOverCause::Wall, score: 10 }, 10, 40),
+            summary(GameStatus::GameOver { cause: GameOverCause::Snake, score: 50 }, 50, 100),
+            summary(GameStatus::Won { cause: WinCause::BoardFilled, score: 300 }, 300, 400),
+            summary(GameStatus::Running, 20, 1000),
+        ];
+        let statistics = BotStatistics::from_summaries("bfs", (8, 6), &summaries);
+
+        assert_eq!((statistics.games, statistics.width, statistics.height), (4, 8, 6));
+        assert_eq!((statistics.mean_score, statistics.median_score, statistics.max_score), (95.0, 35.0, 300));
+        assert_eq!((statistics.mean_ticks, statistics.median_ticks, statistics.max_ticks), (385.0, 250.0, 1000));
+        assert_eq!((statistics.wall_deaths, statistics.snake_deaths, statistics.timeouts), (1, 1, 1));
+        assert_eq!((statistics.wins, statistics.win_rate), (1, 0.25));
+        assert_eq!(BotStatistics::from_summaries("bfs", (8, 6), &summaries[..3]).median_score, 50.0);
+    }
+
+    #[test]
+    fn given_tournament_when_run_then_every_bot_plays_every_board() {
+        let levels = vec![Level::empty(6, 6, Segment::new(3, 3)), Level::empty(8, 4, Segment::new(4, 2))];
+        let tournament = Tournament::new(vec![BotKind::Greedy, BotKind::Hamiltonian], levels, vec![1, 2, 3])
+            .max_ticks(20_000);
+        let statistics = tournament.run();
+
+        let rows: Vec<(&str, usize, usize, usize)> = statistics.iter()
+            .map(|row| (row.bot.as_str(), row.width, row.height, row.games))
+            .collect();
+        assert_eq!(rows, vec![("greedy", 6, 6, 3), ("greedy", 8, 4, 3),
+                              ("hamiltonian", 6, 6, 3), ("hamiltonian", 8, 4, 3)]);
+        assert_eq!(statistics[2].win_rate, 1.0);
+        assert_eq!(statistics, tournament.run());
+    }
+
+    #[test]
+    fn given_statistics_when_reported_then_formats_list_every_row() {
+        let summaries = [summary(GameStatus::Won { cause: WinCause::TargetScore, score: 30 }, 30, 12)];
+        let statistics = vec![BotStatistics::from_summaries("greedy", (10, 10), &summaries)];
+
+        let table = report(&statistics, ReportFormat::Table);
+        assert_eq!(table.lines().count(), 2);
+        assert!(table.lines().nth(1).unwrap().starts_with("greedy         10x10     1"));
+        assert!(table.ends_with("100.0%\n"));
+
+        let csv = report(&statistics, ReportFormat::Csv);
+        assert_eq!(csv.lines().nth(1), Some("greedy,10,10,1,30.00,30.0,30,12.00,12.0,12,0,0,0,1,1.0000"));
+
+        let json: serde_json::Value = serde_json::from_str(&report(&statistics, ReportFormat::Json)).unwrap();
+        assert_eq!(json[0]["bot"], "greedy");
+        assert_eq!(json[0]["wins"], 1);
+        assert_eq!("csv".parse::<ReportFormat>(), Ok(ReportFormat::Csv));
+        assert!("xml".parse::<ReportFormat>().is_err());
+    }
+}
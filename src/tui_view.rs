@@ -0,0 +1,204 @@
+//! Text-mode game view.
+
+use crate::game_logic::GameStatus;
+use crate::game_view::GameViewSettings;
+use crate::renderer::{Frame, Renderer};
+use crate::Obstacle;
+
+/// Stores text-mode view settings.
+pub struct TuiViewSettings {
+    /// Character of an empty field
+    pub empty_char: char,
+    /// Character of the snake head
+    pub snake_head_char: char,
+    /// Character of the snake body
+    pub snake_body_char: char,
+    /// Character of a fruit without a name in game view settings
+    pub unknown_fruit_char: char,
+    /// Character of a wall
+    pub wall_char: char,
+}
+
+impl Default for TuiViewSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TuiViewSettings {
+    /// Creates new text-mode view settings.
+    pub fn new() -> TuiViewSettings {
+        TuiViewSettings {
+            empty_char: '.',
+            snake_head_char: '@',
+            snake_body_char: 'o',
+            unknown_fruit_char: '?',
+            wall_char: '#',
+        }
+    }
+}
+
+/// Renders the game as lines of text, one character per field.
+pub struct TuiView {
+    /// Stores text-mode view settings.
+    pub settings: TuiViewSettings,
+    /// Game view settings, used to name the fruit.
+    pub game_view_settings: GameViewSettings,
+}
+
+impl TuiView {
+    /// Creates a new text-mode view.
+    pub fn new(settings: TuiViewSettings, game_view_settings: GameViewSettings) -> TuiView {
+        TuiView {
+            settings,
+            game_view_settings,
+        }
+    }
+
+    /// Returns the character of a fruit worth `points` - the capitalized first letter of its
+    /// name, e.g. `A` for an apple.
+    pub fn fruit_char(&self, points: u8) -> char {
+        self.game_view_settings.fruit_name(points)
+            .and_then(|name| name.chars().next())
+            .map(|first| first.to_ascii_uppercase())
+            .unwrap_or(self.settings.unknown_fruit_char)
+    }
+
+    /// Renders the board surrounded by a border, followed by a status line.
+    pub fn render_lines(&self, frame: &Frame) -> Vec<String> {
+        let (width, height) = frame.board_size;
+        let mut fields = vec![vec![self.settings.empty_char; width]; height];
+
+        for &(x, y, obstacle) in frame.obstacles.iter() {
+            fields[y][x] = match obstacle {
+                Obstacle::Apple(points) => self.fruit_char(points),
+                Obstacle::Wall => self.settings.wall_char,
+                Obstacle::None => self.settings.empty_char,
+            };
+        }
+        for (index, segment) in frame.snake.iter().enumerate().rev() {
+            if !frame.segment_in(segment) {
+                continue;
+            }
+            fields[segment.y as usize][segment.x as usize] = if index == 0 {
+                self.settings.snake_head_char
+            } else {
+                self.settings.snake_body_char
+            };
+        }
+
+        let border = format!("+{}+", "-".repeat(width));
+        let mut lines = Vec::with_capacity(height + 3);
+        lines.push(border.clone());
+        for row in fields {
+            lines.push(format!("|{}|", row.into_iter().collect::<String>()));
+        }
+        lines.push(border);
+        let status = match frame.status {
+            GameStatus::Running if frame.combo > 1 =>
+                format!("Score: {} Length: {} Level: {} Combo: x{}", frame.score, frame.length, frame.speed_level,
+                        frame.combo),
+            GameStatus::Running =>
+                format!("Score: {} Length: {} Level: {}", frame.score, frame.length, frame.speed_level),
+            GameStatus::GameOver { cause, score } =>
+                format!("Game over: {} Score: {} Length: {} Level: {}", cause, score, frame.length, frame.speed_level),
+            GameStatus::Won { cause, score } =>
+                format!("You won: {} Score: {} Length: {} Level: {}", cause, score, frame.length, frame.speed_level),
+        };
+        lines.push(if frame.paused { format!("Paused {}", status) } else { status });
+        lines
+    }
+}
+
+impl Renderer for TuiView {
+    type Output = Vec<String>;
+
+    fn render(&mut self, frame: &Frame) -> Vec<String> {
+        self.render_lines(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_logic::{GameLogic, WinCause};
+    use crate::{Direction, Segment};
+
+    fn get_view() -> TuiView {
+        TuiView::new(TuiViewSettings::new(), GameViewSettings::new())
+    }
+
+    #[test]
+    fn given_fruit_names_then_fruit_chars_are_first_letters() {
+        let view = get_view();
+        assert_eq!(view.fruit_char(1), 'A');
+        assert_eq!(view.fruit_char(2), 'B');
+        assert_eq!(view.fruit_char(3), 'C');
+        assert_eq!(view.fruit_char(4), '?');
+    }
+
+    #[test]
+    fn given_game_when_rendered_then_snake_and_fruit_are_drawn() {
+        let mut game_logic = GameLogic::with_seed(5, 3, Segment::new(1, 1), 0);
+        game_logic.step(Some(Direction::Right));
+        let obstacles = game_logic.get_obstacles();
+        let lines = get_view().render(&game_logic.frame());
+
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], "+-----+");
+        assert_eq!(lines[4], "+-----+");
+        assert_eq!(lines[2].chars().nth(3), Some('@'));
+        assert!(!obstacles.is_empty());
+        for (x, y, points) in obstacles {
+            assert_eq!(lines[y + 1].chars().nth(x + 1), Some(get_view().fruit_char(points)));
+        }
+        assert_eq!(lines[5], "Score: 0 Length: 1 Level: 1");
+    }
+
+    #[test]
+    fn given_walls_when_rendered_then_walls_are_drawn() {
+        let game_logic = GameLogic::with_seed(4, 2, Segment::new(0, 0), 0).with_walls(&[(1, 0), (3, 1)]);
+        let lines = get_view().render(&game_logic.frame());
+
+        assert_eq!(lines[1], "|@#..|");
+        assert_eq!(lines[2], "|...#|");
+    }
+
+    #[test]
+    fn given_game_over_when_rendered_then_status_line_shows_cause() {
+        let mut game_logic = GameLogic::with_seed(5, 3, Segment::new(0, 1), 0);
+        game_logic.step(Some(Direction::Left));
+        let lines = get_view().render(&game_logic.frame());
+
+        assert_eq!(lines[5], "Game over: You've hit the wall! Score: 0 Length: 1 Level: 1");
+        assert!(!lines.iter().any(|line| line.contains('@')));
+    }
+
+    #[test]
+    fn given_won_game_when_rendered_then_status_line_shows_win() {
+        let mut frame = GameLogic::with_seed(5, 3, Segment::new(0, 1), 0).frame();
+        frame.status = GameStatus::Won { cause: WinCause::BoardFilled, score: 40 };
+        let lines = get_view().render(&frame);
+
+        assert_eq!(lines[5], "You won: You've filled the board! Score: 40 Length: 1 Level: 1");
+    }
+
+    #[test]
+    fn given_paused_game_when_rendered_then_status_line_shows_pause() {
+        let mut frame = GameLogic::with_seed(5, 3, Segment::new(0, 1), 0).frame();
+        frame.paused = true;
+        let lines = get_view().render(&frame);
+
+        assert_eq!(lines[5], "Paused Score: 0 Length: 1 Level: 1");
+    }
+
+    #[test]
+    fn given_faster_game_with_combo_when_rendered_then_status_line_shows_level_and_combo() {
+        let mut frame = GameLogic::with_seed(5, 3, Segment::new(0, 1), 0).frame();
+        frame.speed_level = 3;
+        frame.combo = 2;
+        let lines = get_view().render(&frame);
+
+        assert_eq!(lines[5], "Score: 0 Length: 1 Level: 3 Combo: x2");
+    }
+}